@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+
+const NONE: usize = usize::MAX;
+
+/// Maximum b-matching of a bipartite graph with left nodes `0..lefts_count` and right nodes after them:
+/// `pairs` are (left node, right node) and a node can be in at most `capacities[node]` chosen pairs.
+/// Every phase finds shortest augmenting paths from left nodes with capacity left by BFS and augments along
+/// a blocking set of them, as Hopcroft-Karp does. Paths go from left nodes by pairs not chosen
+/// and from right nodes by chosen ones, and end in a right node with capacity left.
+/// Returns for every pair whether it is chosen, `capacities` are left with what is not used.
+pub fn max_b_matching(pairs: &[(usize, usize)], lefts_count: usize, capacities: &mut [u16]) -> Vec<bool> {
+    let mut node_pairs = vec![Vec::new(); capacities.len()];
+    for (pair, &(left, right)) in pairs.iter().enumerate() {
+        node_pairs[left].push(pair);
+        node_pairs[right].push(pair);
+    }
+    let graph = Graph { pairs, node_pairs, lefts_count };
+
+    let mut chosen = vec![false; pairs.len()];
+    while let Some((levels, shortest)) = layers(&graph, &chosen, capacities) {
+        augment(&graph, levels, shortest, &mut chosen, capacities);
+    }
+    chosen
+}
+
+struct Graph<'a> {
+    pairs: &'a [(usize, usize)],
+    /// node -> pairs it is in
+    node_pairs: Vec<Vec<usize>>,
+    lefts_count: usize,
+}
+
+impl<'a> Graph<'a> {
+    fn is_left(&self, node: usize) -> bool {
+        node < self.lefts_count
+    }
+
+    fn other_end(&self, pair: usize, node: usize) -> usize {
+        let (left, right) = self.pairs[pair];
+        if left == node { right } else { left }
+    }
+
+    /// A left node leaves by a pair not chosen, a right node by a chosen one
+    fn usable(&self, chosen: &[bool], pair: usize, node: usize) -> bool {
+        chosen[pair] != self.is_left(node)
+    }
+
+    fn is_end(&self, capacities: &[u16], node: usize) -> bool {
+        !self.is_left(node) && capacities[node] > 0
+    }
+}
+
+/// Distances of nodes from left nodes with capacity left along alternating paths up to the first right node
+/// with capacity left and the distance of that node, `None` if no augmenting path is left
+fn layers(graph: &Graph, chosen: &[bool], capacities: &[u16]) -> Option<(Vec<usize>, usize)> {
+    let mut levels = vec![NONE; capacities.len()];
+    let mut nodes: VecDeque<usize> = (0..graph.lefts_count).filter(|&left| capacities[left] > 0).collect();
+    for &left in &nodes {
+        levels[left] = 0;
+    }
+
+    let mut shortest = NONE;
+    while let Some(node) = nodes.pop_front() {
+        if levels[node] >= shortest {
+            break;
+        }
+        if graph.is_end(capacities, node) {
+            shortest = levels[node];
+            continue;
+        }
+        for &pair in &graph.node_pairs[node] {
+            let next = graph.other_end(pair, node);
+            if graph.usable(chosen, pair, node) && levels[next] == NONE {
+                levels[next] = levels[node] + 1;
+                nodes.push_back(next);
+            }
+        }
+    }
+    if shortest == NONE { None } else { Some((levels, shortest)) }
+}
+
+/// Flips pairs along shortest augmenting paths from left nodes while they have capacity left.
+/// Depth first search is iterative as paths can be as long as the number of nodes.
+fn augment(graph: &Graph, mut levels: Vec<usize>, shortest: usize, chosen: &mut [bool], capacities: &mut [u16]) {
+    // node -> position of the next pair to try
+    let mut next_pairs = vec![0; capacities.len()];
+    for start in 0..graph.lefts_count {
+        let mut path: Vec<usize> = Vec::new();
+        let mut node = start;
+        while capacities[start] > 0 && levels[start] == 0 {
+            if levels[node] == shortest && graph.is_end(capacities, node) {
+                for &pair in &path {
+                    chosen[pair] = !chosen[pair];
+                }
+                capacities[start] -= 1;
+                capacities[node] -= 1;
+                path.clear();
+                node = start;
+                continue;
+            }
+            if next_pairs[node] == graph.node_pairs[node].len() {
+                // no path goes on from here, leave the node out of the rest of the phase
+                levels[node] = NONE;
+                match path.pop() {
+                    Some(pair) => {
+                        node = graph.other_end(pair, node);
+                        next_pairs[node] += 1;
+                    }
+                    None => break,
+                }
+                continue;
+            }
+            let pair = graph.node_pairs[node][next_pairs[node]];
+            let next = graph.other_end(pair, node);
+            if levels[node] < shortest && graph.usable(chosen, pair, node) && levels[next] == levels[node] + 1 {
+                path.push(pair);
+                node = next;
+            } else {
+                next_pairs[node] += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        // left 0 takes two pairs, right 2 takes two, the others one
+        let pairs = vec![(0, 2), (0, 3), (1, 2), (1, 3)];
+        let mut capacities = vec![2, 1, 2, 1];
+
+        let chosen = max_b_matching(&pairs, 2, &mut capacities);
+
+        assert_eq!(chosen.iter().filter(|&&chosen| chosen).count(), 3);
+        assert_eq!(capacities, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_shortest_paths_only() {
+        // left 0 goes straight to right 3, not through right 2 and left 1 to right 4
+        let pairs = vec![(0, 2), (0, 3), (1, 2), (1, 4)];
+        let mut capacities = vec![1, 0, 0, 1, 1];
+        let mut chosen = vec![false, false, true, false];
+        let node_pairs = vec![vec![0, 1], vec![2, 3], vec![0, 2], vec![1], vec![3]];
+        let graph = Graph { pairs: &pairs, node_pairs, lefts_count: 2 };
+
+        let (levels, shortest) = layers(&graph, &chosen, &capacities).unwrap();
+        assert_eq!(shortest, 1);
+        augment(&graph, levels, shortest, &mut chosen, &mut capacities);
+
+        assert_eq!(chosen, [false, true, true, false]);
+        assert_eq!(capacities, [0, 0, 0, 0, 1]);
+    }
+}
@@ -0,0 +1,140 @@
+use std::convert::TryFrom;
+use std::fmt::Debug;
+
+/// Amount that can flow through an edge.
+/// Arithmetic is checked: overflow is reported as `None` instead of wrapping.
+pub trait Capacity: Copy + PartialOrd + Debug {
+    /// Type of totals over many edges, such as the amount of a flow, wider than `Self` where there is a wider type
+    type Sum: Capacity;
+
+    fn zero() -> Self;
+    fn to_sum(self) -> Self::Sum;
+    /// Back from the wider type, `None` if it does not fit
+    fn from_sum(sum: Self::Sum) -> Option<Self>;
+    /// Whole amount as `i64` for multiplying by costs, `None` if it does not fit or is not whole
+    fn to_i64(self) -> Option<i64>;
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    /// Equal up to rounding errors, which only floating point types have
+    fn close_to(self, other: Self) -> bool;
+}
+
+macro_rules! impl_int_capacity {
+    ($($t:ty => $sum:ty),*) => {
+        $(
+            impl Capacity for $t {
+                type Sum = $sum;
+
+                fn zero() -> Self {
+                    0
+                }
+
+                fn to_sum(self) -> $sum {
+                    self as $sum
+                }
+
+                fn from_sum(sum: $sum) -> Option<Self> {
+                    <$t>::try_from(sum).ok()
+                }
+
+                fn to_i64(self) -> Option<i64> {
+                    i64::try_from(self).ok()
+                }
+
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$t>::checked_add(self, other)
+                }
+
+                fn checked_sub(self, other: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, other)
+                }
+
+                fn close_to(self, other: Self) -> bool {
+                    self == other
+                }
+            }
+        )*
+    }
+}
+
+impl_int_capacity!(u16 => u32, u32 => u64, u64 => u128, u128 => u128, usize => u128,
+                   i32 => i64, i64 => i128, i128 => i128);
+
+const F64_TOLERANCE: f64 = 1e-9;
+
+/// Infinite and NaN results are treated as overflow.
+impl Capacity for f64 {
+    type Sum = f64;
+
+    fn zero() -> Self {
+        0.0
+    }
+
+    fn to_sum(self) -> f64 {
+        self
+    }
+
+    fn from_sum(sum: f64) -> Option<f64> {
+        Some(sum)
+    }
+
+    fn to_i64(self) -> Option<i64> {
+        // i64::MAX as f64 rounds up to 2^63, which does not fit
+        if self.fract() == 0.0 && self >= i64::MIN as f64 && self < i64::MAX as f64 { Some(self as i64) } else { None }
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        finite(self + other)
+    }
+
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        finite(self - other)
+    }
+
+    /// Relative to the larger magnitude, absolute below 1
+    fn close_to(self, other: Self) -> bool {
+        (self - other).abs() <= F64_TOLERANCE * self.abs().max(other.abs()).max(1.0)
+    }
+}
+
+fn finite(f: f64) -> Option<f64> {
+    if f.is_finite() { Some(f) } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64;
+
+    #[test]
+    fn test_int_overflow() {
+        assert_eq!(Capacity::checked_add(65_000u16, 535), Some(65_535));
+        assert_eq!(Capacity::checked_add(65_000u16, 536), None);
+        assert_eq!(Capacity::checked_sub(0u32, 1), None);
+        assert_eq!(Capacity::checked_add(65_000u16.to_sum(), 536), Some(65_536));
+        assert_eq!(u16::from_sum(65_536), None);
+    }
+
+    #[test]
+    fn test_f64_overflow() {
+        assert_eq!(Capacity::checked_add(1.5, 2.0), Some(3.5));
+        assert_eq!(Capacity::checked_add(f64::MAX, f64::MAX), None);
+        assert_eq!(Capacity::checked_sub(f64::INFINITY, 1.0), None);
+    }
+
+    #[test]
+    fn test_close_to() {
+        assert!((0.1 + 0.2).close_to(0.3));
+        assert!(!0.3.close_to(0.31));
+        assert!(!3u32.close_to(4));
+    }
+
+    #[test]
+    fn test_to_i64() {
+        assert_eq!(u64::MAX.to_i64(), None);
+        assert_eq!(5_000_000_000u64.to_i64(), Some(5_000_000_000));
+        assert_eq!(3.0.to_i64(), Some(3));
+        assert_eq!(2.5.to_i64(), None);
+        assert_eq!(f64::NAN.to_i64(), None);
+    }
+}
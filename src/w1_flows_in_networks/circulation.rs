@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+use w1_flows_in_networks::BoundedEdge;
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::VertId;
+use w1_flows_in_networks::add_capacities;
+use w1_flows_in_networks::FlowNetwork;
+use w1_flows_in_networks::sub_capacities;
+use w1_flows_in_networks::super_terminals;
+
+/// Proof that no circulation exists (Hoffman's theorem):
+/// flow entering `verts` through edges or as supply must equal flow leaving them through edges or as demand,
+/// but the possible amounts do not intersect.
+#[derive(Debug)]
+pub struct Infeasibility<C> {
+    pub verts: HashSet<VertId>,
+    /// lower bounds of entering edges + supplies ..= capacities of entering edges + supplies
+    pub entering: RangeInclusive<C>,
+    /// lower bounds of leaving edges + demands ..= capacities of leaving edges + demands
+    pub leaving: RangeInclusive<C>,
+}
+
+/// Finds flows, one per edge, within edge bounds such that in every vertex
+/// inflow + supply = outflow + demand.
+/// Lower bounds are moved to supplies and demands, which are then served by a maximum flow
+/// from a super source to a super sink.
+pub fn circulation<C: Capacity>(edges: &[BoundedEdge<C>], supplies: &HashMap<VertId, C>, demands: &HashMap<VertId, C>)
+                                -> Result<Vec<C>, Infeasibility<C>> {
+    let total_supply = supplies.values().fold(C::zero(), |sum, &supply| add_capacities(sum, supply));
+    let total_demand = demands.values().fold(C::zero(), |sum, &demand| add_capacities(sum, demand));
+    if total_supply != total_demand {
+        let mut verts: HashSet<VertId> = edges.iter().flat_map(|edge| vec![edge.from, edge.to]).collect();
+        verts.extend(supplies.keys());
+        verts.extend(demands.keys());
+        return Err(infeasibility(edges, supplies, demands, verts));
+    }
+
+    // vertex -> flow forced into it by supply and lower bounds of entering edges
+    let mut vert_to_gets: HashMap<VertId, C> = supplies.clone();
+    // vertex -> flow forced out of it by demand and lower bounds of leaving edges
+    let mut vert_to_gives: HashMap<VertId, C> = demands.clone();
+    let mut reduced_edges = Vec::with_capacity(edges.len() + vert_to_gets.len() + vert_to_gives.len());
+    for edge in edges {
+        assert!(edge.lower <= edge.capacity, "lower bound exceeds capacity of edge {:?}", edge);
+        let gets = vert_to_gets.entry(edge.to).or_insert_with(C::zero);
+        *gets = add_capacities(*gets, edge.lower);
+        let gives = vert_to_gives.entry(edge.from).or_insert_with(C::zero);
+        *gives = add_capacities(*gives, edge.lower);
+        reduced_edges.push(Edge { from: edge.from, to: edge.to, capacity: sub_capacities(edge.capacity, edge.lower) });
+    }
+
+    let max_vert = reduced_edges.iter()
+        .map(|edge| edge.from.max(edge.to))
+        .chain(vert_to_gets.keys().cloned())
+        .chain(vert_to_gives.keys().cloned())
+        .max()
+        .unwrap_or(0);
+    let (super_source, super_sink) = super_terminals(max_vert);
+
+    let mut required = C::zero();
+    for (&vert, &gets) in &vert_to_gets {
+        let gives = vert_to_gives.get(&vert).cloned().unwrap_or_else(C::zero);
+        if gets > gives {
+            let surplus = sub_capacities(gets, gives);
+            required = add_capacities(required, surplus);
+            reduced_edges.push(Edge { from: super_source, to: vert, capacity: surplus });
+        }
+    }
+    for (&vert, &gives) in &vert_to_gives {
+        let gets = vert_to_gets.get(&vert).cloned().unwrap_or_else(C::zero);
+        if gives > gets {
+            reduced_edges.push(Edge { from: vert, to: super_sink, capacity: sub_capacities(gives, gets) });
+        }
+    }
+
+    let mut graph = FlowNetwork::new(&reduced_edges);
+    if graph.max_flow(super_source, super_sink) < required.to_sum() {
+        let reachable = graph.reachable_from(super_source as usize);
+        let verts = (0..super_source)
+            .filter(|&vert| reachable[vert as usize])
+            .collect();
+        return Err(infeasibility(edges, supplies, demands, verts));
+    }
+
+    Ok(edges.iter().zip(graph.edge_flows())
+        .map(|(edge, flow)| add_capacities(edge.lower, flow))
+        .collect())
+}
+
+fn infeasibility<C: Capacity>(edges: &[BoundedEdge<C>], supplies: &HashMap<VertId, C>, demands: &HashMap<VertId, C>,
+                              verts: HashSet<VertId>) -> Infeasibility<C> {
+    let mut entering = (C::zero(), C::zero());
+    let mut leaving = (C::zero(), C::zero());
+    for &vert in &verts {
+        if let Some(&supply) = supplies.get(&vert) {
+            entering = (add_capacities(entering.0, supply), add_capacities(entering.1, supply));
+        }
+        if let Some(&demand) = demands.get(&vert) {
+            leaving = (add_capacities(leaving.0, demand), add_capacities(leaving.1, demand));
+        }
+    }
+    for edge in edges {
+        let from_inside = verts.contains(&edge.from);
+        let to_inside = verts.contains(&edge.to);
+        if to_inside && !from_inside {
+            entering = (add_capacities(entering.0, edge.lower), add_capacities(entering.1, edge.capacity));
+        } else if from_inside && !to_inside {
+            leaving = (add_capacities(leaving.0, edge.lower), add_capacities(leaving.1, edge.capacity));
+        }
+    }
+    Infeasibility { verts, entering: entering.0..=entering.1, leaving: leaving.0..=leaving.1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::thread_rng;
+    use w1_flows_in_networks::test_utils::gen_edges;
+
+    #[test]
+    fn test1() {
+        let edges = vec![
+            BoundedEdge { from: 1, to: 2, lower: 2, capacity: 5 },
+            BoundedEdge { from: 2, to: 3, lower: 0, capacity: 1 },
+            BoundedEdge { from: 2, to: 4, lower: 3, capacity: 4 },
+            BoundedEdge { from: 4, to: 1, lower: 1, capacity: 10 },
+        ];
+        let mut supplies = HashMap::new();
+        supplies.insert(4, 1);
+        let mut demands = HashMap::new();
+        demands.insert(3, 1);
+
+        let flows = circulation(&edges, &supplies, &demands).unwrap();
+
+        check_circulation(&edges, &supplies, &demands, &flows);
+        assert_eq!(flows, [4, 1, 3, 4]);
+    }
+
+    #[test]
+    fn test_lower_bounds_too_high() {
+        let edges = vec![
+            BoundedEdge { from: 1, to: 2, lower: 3, capacity: 5 },
+            BoundedEdge { from: 2, to: 1, lower: 0, capacity: 2 },
+        ];
+
+        let infeasibility = circulation(&edges, &HashMap::new(), &HashMap::new()).unwrap_err();
+
+        check_infeasibility(&edges, &HashMap::new(), &HashMap::new(), &infeasibility);
+        assert_eq!(infeasibility.verts, [2].iter().cloned().collect());
+        assert_eq!(infeasibility.entering, 3..=5);
+        assert_eq!(infeasibility.leaving, 0..=2);
+    }
+
+    #[test]
+    fn test_unbalanced() {
+        let edges = vec![
+            BoundedEdge { from: 1, to: 2, lower: 0, capacity: 5 },
+        ];
+        let mut supplies = HashMap::new();
+        supplies.insert(1, 3);
+        let mut demands = HashMap::new();
+        demands.insert(2, 2);
+
+        let infeasibility = circulation(&edges, &supplies, &demands).unwrap_err();
+
+        check_infeasibility(&edges, &supplies, &demands, &infeasibility);
+        assert_eq!(infeasibility.verts, [1, 2].iter().cloned().collect());
+    }
+
+    #[test]
+    fn test_rnd() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let edges: Vec<BoundedEdge<u32>> = gen_edges(8, 20, 10).iter()
+                .map(|edge| {
+                    let lower = rng.gen_range(0, edge.capacity / 2 + 1);
+                    BoundedEdge { from: edge.from, to: edge.to, lower, capacity: edge.capacity }
+                })
+                .collect();
+            let mut supplies = HashMap::new();
+            let mut demands = HashMap::new();
+            for _ in 0..rng.gen_range(0, 3) {
+                let amount = rng.gen_range(1, 5);
+                *supplies.entry(rng.gen_range(1, 9)).or_insert(0) += amount;
+                *demands.entry(rng.gen_range(1, 9)).or_insert(0) += amount;
+            }
+
+            match circulation(&edges, &supplies, &demands) {
+                Ok(flows) => check_circulation(&edges, &supplies, &demands, &flows),
+                Err(infeasibility) => check_infeasibility(&edges, &supplies, &demands, &infeasibility),
+            }
+        }
+    }
+
+    fn check_circulation(edges: &[BoundedEdge<u32>], supplies: &HashMap<VertId, u32>, demands: &HashMap<VertId, u32>,
+                         flows: &[u32]) {
+        assert_eq!(flows.len(), edges.len());
+        let mut vert_to_balance: HashMap<VertId, i64> = HashMap::new();
+        for (edge, &flow) in edges.iter().zip(flows) {
+            assert!(edge.lower <= flow && flow <= edge.capacity, "flow {} on edge {:?}", flow, edge);
+            *vert_to_balance.entry(edge.from).or_insert(0) -= flow as i64;
+            *vert_to_balance.entry(edge.to).or_insert(0) += flow as i64;
+        }
+        for (&vert, &supply) in supplies {
+            *vert_to_balance.entry(vert).or_insert(0) += supply as i64;
+        }
+        for (&vert, &demand) in demands {
+            *vert_to_balance.entry(vert).or_insert(0) -= demand as i64;
+        }
+        for (vert, balance) in vert_to_balance {
+            assert_eq!(balance, 0, "vertex {} is not balanced, edges {:?}, flows {:?}", vert, edges, flows);
+        }
+    }
+
+    fn check_infeasibility(edges: &[BoundedEdge<u32>], supplies: &HashMap<VertId, u32>, demands: &HashMap<VertId, u32>,
+                           infeasibility: &Infeasibility<u32>) {
+        let expected = super::infeasibility(edges, supplies, demands, infeasibility.verts.clone());
+        assert_eq!(infeasibility.entering, expected.entering);
+        assert_eq!(infeasibility.leaving, expected.leaving);
+        let entering = &infeasibility.entering;
+        let leaving = &infeasibility.leaving;
+        assert!(entering.start() > leaving.end() || leaving.start() > entering.end(),
+                "not a proof: {:?}, edges {:?}, supplies {:?}, demands {:?}", infeasibility, edges, supplies, demands);
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::Flows;
+use w1_flows_in_networks::VertId;
+use w1_flows_in_networks::add_capacities;
+use w1_flows_in_networks::sub_capacities;
+
+/// `amount` sent along `verts`, a cycle starts and ends with the same vertex
+#[derive(Debug, PartialEq)]
+pub struct FlowPath<C> {
+    pub verts: Vec<VertId>,
+    pub amount: C,
+}
+
+#[derive(Debug)]
+pub struct Decomposition<C> {
+    /// from the source to the sink
+    pub paths: Vec<FlowPath<C>>,
+    /// flow that goes around and never reaches the sink
+    pub cycles: Vec<FlowPath<C>>,
+}
+
+impl<C: Capacity> Decomposition<C> {
+    /// Sum of amounts of all paths and cycles on every edge
+    pub fn flows(&self) -> Flows<C> {
+        let mut vert_to_flows: Flows<C> = HashMap::new();
+        for path in self.paths.iter().chain(&self.cycles) {
+            for pair in path.verts.windows(2) {
+                let flow = vert_to_flows.entry(pair[0]).or_default().entry(pair[1]).or_insert_with(C::zero);
+                *flow = add_capacities(*flow, path.amount);
+            }
+        }
+        vert_to_flows
+    }
+
+    /// Whether paths and cycles add back up to exactly the given flows
+    pub fn adds_up_to(&self, vert_to_flows: &Flows<C>) -> bool {
+        let non_zero = |vert_to_flows: &Flows<C>| -> HashMap<(VertId, VertId), C> {
+            vert_to_flows.iter()
+                .flat_map(|(&from, flows)| flows.iter().map(move |(&to, &flow)| ((from, to), flow)))
+                .filter(|&(_, flow)| flow != C::zero())
+                .collect()
+        };
+        non_zero(&self.flows()) == non_zero(vert_to_flows)
+    }
+}
+
+/// Splits flows into paths from the source to the sink and cycles.
+/// Panics if flow is not conserved in a vertex other than the source and the sink. Zero flows are ignored.
+pub fn decompose<C: Capacity>(vert_to_flows: &Flows<C>, from: VertId, to: VertId) -> Decomposition<C> {
+    // a walk must not follow an edge without flow, as it would find nothing leaving its end
+    let mut left: Flows<C> = HashMap::new();
+    for (&vert, flows) in vert_to_flows {
+        for (&next, &flow) in flows {
+            if flow != C::zero() {
+                left.entry(vert).or_default().insert(next, flow);
+            }
+        }
+    }
+    let mut paths = Vec::new();
+    let mut cycles = Vec::new();
+
+    while from != to && left.contains_key(&from) {
+        let (path, cycle) = walk(&mut left, from, Some(to));
+        cycles.extend(cycle);
+        if let Some(path) = path {
+            paths.push(path);
+        }
+    }
+
+    while let Some(&start) = left.keys().next() {
+        let (_, cycle) = walk(&mut left, start, None);
+        cycles.extend(cycle);
+    }
+
+    Decomposition { paths, cycles }
+}
+
+/// Follows flows from the start until the sink or a vertex already on the way.
+/// Returns a path to the sink, if one was found, or a cycle, in both cases with their flow removed.
+fn walk<C: Capacity>(left: &mut Flows<C>, start: VertId, sink: Option<VertId>)
+                     -> (Option<FlowPath<C>>, Option<FlowPath<C>>) {
+    let mut verts = vec![start];
+    let mut vert_to_pos = HashMap::new();
+    vert_to_pos.insert(start, 0);
+    loop {
+        let vert = *verts.last().unwrap();
+        if Some(vert) == sink {
+            return (Some(remove_flow(left, verts)), None);
+        }
+        let next = match left.get(&vert).and_then(|flows| flows.keys().next()) {
+            Some(&next) => next,
+            None => panic!("flow is not conserved in vertex {}", vert),
+        };
+        if let Some(&pos) = vert_to_pos.get(&next) {
+            let mut cycle = verts.split_off(pos);
+            cycle.push(next);
+            return (None, Some(remove_flow(left, cycle)));
+        }
+        vert_to_pos.insert(next, verts.len());
+        verts.push(next);
+    }
+}
+
+/// Subtracts the minimum flow along the vertices from all their edges, drops edges left without flow
+fn remove_flow<C: Capacity>(left: &mut Flows<C>, verts: Vec<VertId>) -> FlowPath<C> {
+    let mut amount = left[&verts[0]][&verts[1]];
+    for pair in verts.windows(2).skip(1) {
+        let flow = left[&pair[0]][&pair[1]];
+        if flow < amount {
+            amount = flow;
+        }
+    }
+
+    for pair in verts.windows(2) {
+        let mut remove_from_flows = false;
+        {
+            let flows = left.get_mut(&pair[0]).unwrap();
+            let flow = sub_capacities(flows[&pair[1]], amount);
+            if flow == C::zero() {
+                flows.remove(&pair[1]);
+                remove_from_flows = flows.is_empty();
+            } else {
+                flows.insert(pair[1], flow);
+            }
+        }
+        if remove_from_flows {
+            left.remove(&pair[0]);
+        }
+    }
+
+    FlowPath { verts, amount }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::thread_rng;
+    use w1_flows_in_networks::max_flow;
+    use w1_flows_in_networks::test_utils::evacuation_graph;
+    use w1_flows_in_networks::test_utils::for_rnd_edges;
+
+    #[test]
+    fn test1() {
+        let graph = evacuation_graph();
+        let flows = max_flow(&graph, 1, 5);
+
+        let decomposition = decompose(&flows, 1, 5);
+
+        assert!(decomposition.adds_up_to(&flows));
+        assert!(decomposition.cycles.is_empty());
+        assert_eq!(decomposition.paths.iter().map(|path| path.amount).sum::<u32>(), 6);
+        for path in &decomposition.paths {
+            assert_eq!(path.verts.first(), Some(&1));
+            assert_eq!(path.verts.last(), Some(&5));
+        }
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut flows: Flows<u16> = HashMap::new();
+        flows.entry(1).or_default().insert(2, 3);
+        flows.entry(2).or_default().insert(3, 5);
+        flows.entry(3).or_default().insert(4, 3);
+        flows.entry(3).or_default().insert(5, 2);
+        flows.entry(5).or_default().insert(2, 2);
+
+        let decomposition = decompose(&flows, 1, 4);
+
+        assert!(decomposition.adds_up_to(&flows));
+        assert_eq!(decomposition.paths, [FlowPath { verts: vec![1, 2, 3, 4], amount: 3 }]);
+        assert_eq!(decomposition.cycles.len(), 1);
+        assert_eq!(decomposition.cycles[0].amount, 2);
+        let cycle = &decomposition.cycles[0].verts;
+        assert_eq!(cycle.first(), cycle.last());
+        assert_eq!(cycle.len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "flow is not conserved in vertex 2")]
+    fn test_not_conserved() {
+        let mut flows: Flows<u16> = HashMap::new();
+        flows.entry(1).or_default().insert(2, 3);
+
+        decompose(&flows, 1, 3);
+    }
+
+    #[test]
+    fn test_zero_flows() {
+        let mut flows: Flows<u16> = HashMap::new();
+        flows.entry(1).or_default().insert(2, 0);
+        flows.entry(1).or_default().insert(3, 4);
+        flows.entry(3).or_default().insert(4, 4);
+        flows.entry(5).or_default().insert(1, 0);
+
+        let decomposition = decompose(&flows, 1, 4);
+
+        assert!(decomposition.adds_up_to(&flows));
+        assert_eq!(decomposition.paths, [FlowPath { verts: vec![1, 3, 4], amount: 4 }]);
+        assert!(decomposition.cycles.is_empty());
+    }
+
+    #[test]
+    fn test_rnd() {
+        let mut rng = thread_rng();
+        for_rnd_edges(|edges, expected| {
+            let mut flows = max_flow(edges, 1, 2);
+            // circulate some flow around a random cycle too
+            let cycle: Vec<VertId> = (0..rng.gen_range(2, 5)).map(|_| rng.gen_range(3, 11)).collect();
+            for i in 0..cycle.len() {
+                let (from, to) = (cycle[i], cycle[(i + 1) % cycle.len()]);
+                if from != to {
+                    *flows.entry(from).or_default().entry(to).or_insert(0) += 1;
+                }
+            }
+
+            let decomposition = decompose(&flows, 1, 2);
+
+            assert!(decomposition.adds_up_to(&flows), "flows {:?}, decomposition {:?}", flows, decomposition);
+            let amount: u64 = decomposition.paths.iter().map(|path| path.amount).sum();
+            assert_eq!(amount, expected);
+            for cycle in &decomposition.cycles {
+                assert_eq!(cycle.verts.first(), cycle.verts.last());
+            }
+        });
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::VecDeque;
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::Flows;
+use w1_flows_in_networks::VertId;
+use w1_flows_in_networks::FlowNetwork;
+use w1_flows_in_networks::add_capacities;
+use w1_flows_in_networks::min_capacity;
+
+const NO_LEVEL: usize = usize::MAX;
+
+/// Same result as `w1_flows_in_networks::max_flow`, but in O(V^2 * E) time:
+/// each phase builds a level graph by BFS and saturates it with a blocking flow.
+pub fn max_flow<C: Capacity>(edges: &[Edge<C>], from: VertId, to: VertId) -> Flows<C::Sum> {
+    let mut network = FlowNetwork::new(edges);
+    network.max_flow(from, to);
+    network.flows()
+}
+
+/// Augments flow until the sink is unreachable from the source, returns the amount added
+pub(super) fn saturate<C: Capacity>(graph: &mut FlowNetwork<C>, source: usize, sink: usize) -> C::Sum {
+    let mut amount = C::Sum::zero();
+    if source != sink {
+        while let Some(levels) = levels(graph, source, sink) {
+            amount = add_capacities(amount, blocking_flow(graph, &levels, source, sink));
+        }
+    }
+    amount
+}
+
+/// Distances from the source over non-saturated arcs or `None` if the sink is unreachable.
+fn levels<C: Capacity>(graph: &FlowNetwork<C>, source: usize, sink: usize) -> Option<Vec<usize>> {
+    let mut levels = vec![NO_LEVEL; graph.out_arcs.len()];
+    levels[source] = 0;
+    let mut verts = VecDeque::new();
+    verts.push_back(source);
+    while let Some(vert) = verts.pop_front() {
+        for &arc in &graph.out_arcs[vert] {
+            let head = graph.heads[arc];
+            if levels[head] == NO_LEVEL && graph.capacities[arc] > C::zero() {
+                levels[head] = levels[vert] + 1;
+                verts.push_back(head);
+            }
+        }
+    }
+    if levels[sink] == NO_LEVEL { None } else { Some(levels) }
+}
+
+/// Returns the amount of flow added
+fn blocking_flow<C: Capacity>(graph: &mut FlowNetwork<C>, levels: &[usize], source: usize, sink: usize) -> C::Sum {
+    // vert -> index of the first out arc that may still lead to the sink
+    let mut next_arcs = vec![0; graph.out_arcs.len()];
+    let mut path: Vec<usize> = Vec::new();
+    let mut vert = source;
+    let mut total_amount = C::Sum::zero();
+    loop {
+        if vert == sink {
+            let amount = min_capacity(graph, &path);
+            for &arc in &path {
+                graph.push(arc, amount);
+            }
+            total_amount = add_capacities(total_amount, amount.to_sum());
+            // retreat to the tail of the first saturated arc and continue from there,
+            // an undirected edge that only took its flow back is not saturated and the same path goes on
+            if let Some(saturated_i) = path.iter().position(|&arc| graph.capacities[arc] == C::zero()) {
+                vert = graph.tail(path[saturated_i]);
+                path.truncate(saturated_i);
+            }
+            continue;
+        }
+
+        let mut next_arc = None;
+        while next_arcs[vert] < graph.out_arcs[vert].len() {
+            let arc = graph.out_arcs[vert][next_arcs[vert]];
+            if graph.capacities[arc] > C::zero() && levels[graph.heads[arc]] == levels[vert] + 1 {
+                next_arc = Some(arc);
+                break;
+            }
+            next_arcs[vert] += 1;
+        }
+
+        match next_arc {
+            Some(arc) => {
+                path.push(arc);
+                vert = graph.heads[arc];
+            }
+            None => {
+                // dead end: never come back to this vertex in this phase
+                match path.pop() {
+                    Some(arc) => {
+                        vert = graph.tail(arc);
+                        next_arcs[vert] += 1;
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+    total_amount
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use w1_flows_in_networks::test_utils::check_max_flow_rnd;
+    use w1_flows_in_networks::test_utils::evacuation_graph;
+    use w1_flows_in_networks::test_utils::flow_amount;
+
+    #[test]
+    fn test1() {
+        let flows = max_flow(&evacuation_graph(), 1, 5);
+
+        assert_eq!(flow_amount(&flows, 1), 6);
+    }
+
+    #[test]
+    fn test_antiparallel_edges() {
+        let graph: Vec<Edge> = vec![
+            Edge { from: 1, to: 2, capacity: 3 },
+            Edge { from: 2, to: 1, capacity: 3 },
+            Edge { from: 2, to: 3, capacity: 2 },
+            Edge { from: 2, to: 2, capacity: 7 },
+        ];
+
+        let flows = max_flow(&graph, 1, 3);
+
+        assert_eq!(flows.get(&1).unwrap().get(&2), Some(&2));
+        assert_eq!(flows.get(&2).unwrap().get(&1), None);
+        assert_eq!(flows.get(&2).unwrap().get(&3), Some(&2));
+    }
+
+    #[test]
+    fn test_rnd() {
+        check_max_flow_rnd(max_flow);
+    }
+}
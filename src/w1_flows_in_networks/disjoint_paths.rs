@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::FlowNetwork;
+use w1_flows_in_networks::Flows;
+use w1_flows_in_networks::VertId;
+use w1_flows_in_networks::decomposition::decompose;
+use w1_flows_in_networks::p1_evacuating_people::max_flow_with_vert_capacities;
+
+/// Maximum number of paths from one vertex to another that share no link, as vertex sequences.
+/// Links are directed, parallel links can carry a path each.
+pub fn edge_disjoint_paths(links: &[(VertId, VertId)], from: VertId, to: VertId) -> Vec<Vec<VertId>> {
+    assert!(from != to, "paths from vertex {} to itself", from);
+    let edges: Vec<Edge> = links.iter().map(|&(from, to)| Edge { from, to, capacity: 1 }).collect();
+    let mut network = FlowNetwork::new(&edges);
+    network.max_flow(from, to);
+    paths(&network.flows(), from, to)
+}
+
+/// Maximum number of paths from one vertex to another that share no vertex but the ends, as vertex sequences.
+/// Links are directed.
+pub fn vert_disjoint_paths(links: &[(VertId, VertId)], from: VertId, to: VertId) -> Vec<Vec<VertId>> {
+    assert!(from != to, "paths from vertex {} to itself", from);
+    // a link to the sink can not be taken twice as it would repeat a path
+    let links: HashSet<&(VertId, VertId)> = links.iter().collect();
+    let edges: Vec<Edge> = links.iter().map(|&&(from, to)| Edge { from, to, capacity: 1 }).collect();
+    let vert_capacities: HashMap<VertId, u16> = edges.iter()
+        .flat_map(|edge| vec![edge.from, edge.to])
+        .filter(|&vert| vert != from && vert != to)
+        .map(|vert| (vert, 1))
+        .collect();
+    paths(&max_flow_with_vert_capacities(&edges, &vert_capacities, from, to), from, to)
+}
+
+/// Unit flow paths, a path that carries more is repeated, cycles are dropped
+fn paths(vert_to_flows: &Flows<u32>, from: VertId, to: VertId) -> Vec<Vec<VertId>> {
+    decompose(vert_to_flows, from, to).paths.into_iter()
+        .flat_map(|path| vec![path.verts; path.amount as usize])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::thread_rng;
+    use w1_flows_in_networks::p1_evacuating_people::max_flow_amount;
+    use w1_flows_in_networks::p1_evacuating_people::max_flow_amount_with_vert_capacities;
+
+    #[test]
+    fn test1() {
+        let links = vec![(1, 2), (1, 3), (2, 4), (3, 4), (2, 3), (4, 5), (4, 5), (1, 5)];
+
+        let edge_disjoint = edge_disjoint_paths(&links, 1, 5);
+        let vert_disjoint = vert_disjoint_paths(&links, 1, 5);
+
+        assert_eq!(edge_disjoint.len(), 3);
+        check_paths(&links, &edge_disjoint, 1, 5);
+        assert_eq!(vert_disjoint.len(), 2);
+        assert!(vert_disjoint.contains(&vec![1, 5]));
+        check_paths(&links, &vert_disjoint, 1, 5);
+    }
+
+    #[test]
+    fn test_parallel_links() {
+        let links = vec![(1, 2), (1, 2), (2, 3), (2, 3), (2, 3)];
+
+        assert_eq!(edge_disjoint_paths(&links, 1, 3), [vec![1, 2, 3], vec![1, 2, 3]]);
+        assert_eq!(vert_disjoint_paths(&links, 1, 3), [vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn test_rnd() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let verts_count = rng.gen_range(2, 11);
+            let links: Vec<(VertId, VertId)> = (0..rng.gen_range(0, 31))
+                .map(|_| (rng.gen_range(1, verts_count + 1), rng.gen_range(1, verts_count + 1)))
+                .collect();
+            let edges: Vec<Edge> = links.iter().map(|&(from, to)| Edge { from, to, capacity: 1 }).collect();
+
+            let edge_disjoint = edge_disjoint_paths(&links, 1, 2);
+            check_paths(&links, &edge_disjoint, 1, 2);
+            assert_eq!(edge_disjoint.len() as u32, max_flow_amount(&edges, 1, 2), "links {:?}", links);
+            let mut link_to_uses: HashMap<(VertId, VertId), i32> = HashMap::new();
+            for &link in &links {
+                *link_to_uses.entry(link).or_insert(0) += 1;
+            }
+            for path in &edge_disjoint {
+                for pair in path.windows(2) {
+                    let uses = link_to_uses.get_mut(&(pair[0], pair[1])).unwrap();
+                    *uses -= 1;
+                    assert!(*uses >= 0, "link {:?} is used too often, links {:?}", pair, links);
+                }
+            }
+
+            let vert_disjoint = vert_disjoint_paths(&links, 1, 2);
+            check_paths(&links, &vert_disjoint, 1, 2);
+            let unit_edges: Vec<Edge> = links.iter().collect::<HashSet<_>>().iter()
+                .map(|&&(from, to)| Edge { from, to, capacity: 1 })
+                .collect();
+            let vert_capacities = (3..verts_count + 1).map(|vert| (vert, 1)).collect();
+            let expected = max_flow_amount_with_vert_capacities(&unit_edges, &vert_capacities, 1, 2);
+            assert_eq!(vert_disjoint.len() as u32, expected, "links {:?}", links);
+            let mut visited = HashSet::new();
+            for path in &vert_disjoint {
+                for &vert in &path[1..path.len() - 1] {
+                    assert!(visited.insert(vert), "vertex {} is visited twice, links {:?}", vert, links);
+                }
+            }
+            let unique_paths: HashSet<&Vec<VertId>> = vert_disjoint.iter().collect();
+            assert_eq!(unique_paths.len(), vert_disjoint.len());
+        }
+    }
+
+    fn check_paths(links: &[(VertId, VertId)], paths: &[Vec<VertId>], from: VertId, to: VertId) {
+        for path in paths {
+            assert_eq!(path.first(), Some(&from));
+            assert_eq!(path.last(), Some(&to));
+            for pair in path.windows(2) {
+                assert!(links.contains(&(pair[0], pair[1])), "no link {:?} in {:?}", pair, links);
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fmt::Write;
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::Flows;
+use w1_flows_in_networks::VertId;
+use w1_flows_in_networks::sub_capacities;
+
+const SOURCE_SIDE_COLOR: &str = "lightblue";
+const SINK_SIDE_COLOR: &str = "lightpink";
+const SATURATED_COLOR: &str = "red";
+
+/// Renders edges in Graphviz DOT. Edges are labelled `flow/capacity` if flows are given, saturated ones are red.
+/// If the source side of a cut is given, its vertices are filled blue and the rest pink.
+pub fn to_dot<C: Capacity + Display>(edges: &[Edge<C>], vert_to_flows: Option<&Flows<C::Sum>>,
+                                     source_side: Option<&HashSet<VertId>>) -> String where C::Sum: Display {
+    let mut dot = String::from("digraph {\n");
+
+    let mut verts: Vec<VertId> = edges.iter().flat_map(|edge| vec![edge.from, edge.to]).collect();
+    verts.sort();
+    verts.dedup();
+    for vert in verts {
+        match source_side {
+            Some(source_side) => {
+                let color = if source_side.contains(&vert) { SOURCE_SIDE_COLOR } else { SINK_SIDE_COLOR };
+                writeln!(dot, "    {} [style=filled, fillcolor={}];", vert, color).unwrap();
+            }
+            None => writeln!(dot, "    {};", vert).unwrap(),
+        }
+    }
+
+    // flow between two vertices is split among parallel edges in their order
+    let mut flows_left: HashMap<(VertId, VertId), C::Sum> = HashMap::new();
+    if let Some(vert_to_flows) = vert_to_flows {
+        for (&from, flows) in vert_to_flows {
+            for (&to, &flow) in flows {
+                flows_left.insert((from, to), flow);
+            }
+        }
+    }
+    for edge in edges {
+        match vert_to_flows {
+            Some(_) => {
+                let left = flows_left.entry((edge.from, edge.to)).or_insert_with(C::Sum::zero);
+                let capacity = edge.capacity.to_sum();
+                let flow = if *left < capacity { *left } else { capacity };
+                *left = sub_capacities(*left, flow);
+                let saturated = flow == capacity && edge.capacity > C::zero();
+                let highlight = if saturated { format!(", color={}, penwidth=2", SATURATED_COLOR) } else { String::new() };
+                writeln!(dot, "    {} -> {} [label=\"{}/{}\"{}];", edge.from, edge.to, flow, edge.capacity, highlight).unwrap();
+            }
+            None => writeln!(dot, "    {} -> {} [label=\"{}\"];", edge.from, edge.to, edge.capacity).unwrap(),
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use w1_flows_in_networks::max_flow;
+    use w1_flows_in_networks::min_cut::min_cut;
+
+    #[test]
+    fn test1() {
+        let graph = vec![
+            Edge { from: 1, to: 2, capacity: 2 },
+            Edge { from: 1, to: 3, capacity: 1 },
+            Edge { from: 2, to: 3, capacity: 3 },
+        ];
+        let flows = max_flow(&graph, 1, 3);
+        let min_cut = min_cut(&graph, 1, 3);
+
+        let dot = to_dot(&graph, Some(&flows), Some(&min_cut.source_side));
+
+        let lines: Vec<&str> = dot.lines().collect();
+        assert_eq!(lines, [
+            "digraph {",
+            "    1 [style=filled, fillcolor=lightblue];",
+            "    2 [style=filled, fillcolor=lightpink];",
+            "    3 [style=filled, fillcolor=lightpink];",
+            "    1 -> 2 [label=\"2/2\", color=red, penwidth=2];",
+            "    1 -> 3 [label=\"1/1\", color=red, penwidth=2];",
+            "    2 -> 3 [label=\"2/3\"];",
+            "}",
+        ]);
+    }
+
+    #[test]
+    fn test_without_flows() {
+        let graph = vec![
+            Edge { from: 2, to: 1, capacity: 5 },
+        ];
+
+        assert_eq!(to_dot(&graph, None, None), "digraph {\n    1;\n    2;\n    2 -> 1 [label=\"5\"];\n}\n");
+    }
+
+    #[test]
+    fn test_parallel_edges() {
+        let graph: Vec<Edge> = vec![
+            Edge { from: 1, to: 2, capacity: 2 },
+            Edge { from: 1, to: 2, capacity: 4 },
+        ];
+        let mut flows: Flows<u32> = HashMap::new();
+        flows.entry(1).or_default().insert(2, 3);
+
+        let dot = to_dot(&graph, Some(&flows), None);
+
+        assert!(dot.contains("1 -> 2 [label=\"2/2\", color=red, penwidth=2];"));
+        assert!(dot.contains("1 -> 2 [label=\"1/4\"];"));
+    }
+}
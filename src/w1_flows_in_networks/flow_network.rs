@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::Flows;
+use w1_flows_in_networks::VertId;
+use w1_flows_in_networks::add_capacities;
+use w1_flows_in_networks::dinic;
+use w1_flows_in_networks::sub_capacities;
+use w1_flows_in_networks::update_flows;
+
+/// Flow network in adjacency-list form indexed by vertex id, keeps its flow between max flow runs.
+/// Edge `i` is stored as arc `2 * i` and its reverse as arc `2 * i + 1`,
+/// so flow on the edge equals the residual capacity of the reverse arc.
+/// Both arcs of an undirected edge start with its capacity and have it less the flow going their way left.
+/// Flow only goes one way at a time: pushing against it takes it back first, so no arc holds more than the capacity.
+#[derive(Debug)]
+pub struct FlowNetwork<C = u16> {
+    /// vertex -> ids of arcs going out of it
+    pub(super) out_arcs: Vec<Vec<usize>>,
+    /// arc -> to vertex
+    pub(super) heads: Vec<usize>,
+    /// arc -> residual capacity
+    pub(super) capacities: Vec<C>,
+    /// edge -> capacity shared by both directions if the edge is undirected
+    shared_capacities: Vec<Option<C>>,
+}
+
+impl<C: Capacity> FlowNetwork<C> {
+    /// Edge indices follow the order of the slice
+    pub fn new(edges: &[Edge<C>]) -> FlowNetwork<C> {
+        let mut network = FlowNetwork {
+            out_arcs: Vec::new(),
+            heads: Vec::with_capacity(edges.len() * 2),
+            capacities: Vec::with_capacity(edges.len() * 2),
+            shared_capacities: Vec::with_capacity(edges.len()),
+        };
+        for edge in edges {
+            network.add_edge(edge.from, edge.to, edge.capacity);
+        }
+        network
+    }
+
+    /// Makes sure the vertex is in the network even if no edge touches it
+    pub fn add_vert(&mut self, vert: VertId) {
+        self.add_vert_index(vert as usize);
+    }
+
+    /// Returns index of the new edge, negative capacity is treated as zero
+    pub fn add_edge(&mut self, from: VertId, to: VertId, capacity: C) -> usize {
+        self.add_edge_by_index(from as usize, to as usize, capacity)
+    }
+
+    /// Same as `add_edge` for networks with more vertices than `VertId` holds,
+    /// their flows can only be read by edge and by vertex index
+    pub(super) fn add_edge_by_index(&mut self, from: usize, to: usize, capacity: C) -> usize {
+        self.add_vert_index(from.max(to));
+        let capacity = if capacity > C::zero() { capacity } else { C::zero() };
+        self.add_arc(from, to, capacity);
+        self.add_arc(to, from, C::zero());
+        self.shared_capacities.push(None);
+        self.edges_count() - 1
+    }
+
+    fn add_vert_index(&mut self, vert: usize) {
+        if self.out_arcs.len() <= vert {
+            self.out_arcs.resize(vert + 1, Vec::new());
+        }
+    }
+
+    /// Returns index of the new edge, which can carry up to `capacity` either way
+    pub fn add_undirected_edge(&mut self, a: VertId, b: VertId, capacity: C) -> usize {
+        self.add_vert(a.max(b));
+        let capacity = if capacity > C::zero() { capacity } else { C::zero() };
+        self.add_arc(a as usize, b as usize, capacity);
+        self.add_arc(b as usize, a as usize, capacity);
+        self.shared_capacities.push(Some(capacity));
+        self.edges_count() - 1
+    }
+
+    fn add_arc(&mut self, from: usize, to: usize, capacity: C) {
+        self.out_arcs[from].push(self.heads.len());
+        self.heads.push(to);
+        self.capacities.push(capacity);
+    }
+
+    pub fn edges_count(&self) -> usize {
+        self.heads.len() / 2
+    }
+
+    /// Amount of flow on the edge, see `directed_flow` for its direction
+    pub fn flow(&self, edge: usize) -> C {
+        self.directed_flow(edge).2
+    }
+
+    /// (from vertex, to vertex, amount) of flow on the edge, an undirected edge reports the way its net flow goes
+    pub fn directed_flow(&self, edge: usize) -> (VertId, VertId, C) {
+        let arc = 2 * edge;
+        let tail = self.tail(arc) as VertId;
+        let head = self.heads[arc] as VertId;
+        match self.shared_capacities[edge] {
+            None => (tail, head, self.capacities[arc + 1]),
+            Some(capacity) => {
+                let backward = sub_capacities(capacity, self.capacities[arc + 1]);
+                if backward > C::zero() {
+                    (head, tail, backward)
+                } else {
+                    (tail, head, sub_capacities(capacity, self.capacities[arc]))
+                }
+            }
+        }
+    }
+
+    /// Augments the current flow to a maximum one with Dinic's algorithm, returns the amount added
+    pub fn max_flow(&mut self, from: VertId, to: VertId) -> C::Sum {
+        self.max_flow_by_index(from as usize, to as usize)
+    }
+
+    /// Same as `max_flow` for vertex indices beyond `VertId`
+    pub(super) fn max_flow_by_index(&mut self, from: usize, to: usize) -> C::Sum {
+        self.add_vert_index(from.max(to));
+        dinic::saturate(self, from, to)
+    }
+
+    /// Flow of every edge in the order edges were added
+    pub fn edge_flows(&self) -> Vec<C> {
+        (0..self.edges_count()).map(|edge| self.flow(edge)).collect()
+    }
+
+    /// Removes all flow
+    pub fn reset(&mut self) {
+        for (edge, shared_capacity) in self.shared_capacities.iter().enumerate() {
+            let arc = 2 * edge;
+            match *shared_capacity {
+                None => {
+                    self.capacities[arc] = add_capacities(self.capacities[arc], self.capacities[arc + 1]);
+                    self.capacities[arc + 1] = C::zero();
+                }
+                Some(capacity) => {
+                    self.capacities[arc] = capacity;
+                    self.capacities[arc + 1] = capacity;
+                }
+            }
+        }
+    }
+
+    /// Net flows: if flow goes both ways between two vertices only the difference is kept.
+    /// Parallel edges are merged, so amounts are `C::Sum`.
+    pub fn flows(&self) -> Flows<C::Sum> {
+        let mut vert_to_flows = HashMap::new();
+        for edge in 0..self.edges_count() {
+            let (from, to, flow) = self.directed_flow(edge);
+            if flow > C::zero() {
+                update_flows(&mut vert_to_flows, &[from, to], flow.to_sum());
+            }
+        }
+        vert_to_flows
+    }
+
+    pub(super) fn tail(&self, arc: usize) -> usize {
+        self.heads[arc ^ 1]
+    }
+
+    pub(super) fn push(&mut self, arc: usize, amount: C) {
+        match self.shared_capacities[arc / 2] {
+            None => {
+                self.capacities[arc] = sub_capacities(self.capacities[arc], amount);
+                self.capacities[arc ^ 1] = add_capacities(self.capacities[arc ^ 1], amount);
+            }
+            Some(capacity) => {
+                // flow going the other way is taken back, only the rest goes this way
+                let backward = sub_capacities(capacity, self.capacities[arc ^ 1]);
+                if amount <= backward {
+                    self.capacities[arc ^ 1] = add_capacities(self.capacities[arc ^ 1], amount);
+                } else {
+                    self.capacities[arc ^ 1] = capacity;
+                    self.capacities[arc] = sub_capacities(self.capacities[arc], sub_capacities(amount, backward));
+                }
+            }
+        }
+    }
+
+    /// vertex -> whether it can be reached from the vertex by non-saturated arcs
+    pub(super) fn reachable_from(&self, vert: usize) -> Vec<bool> {
+        let mut reachable = vec![false; self.out_arcs.len()];
+        reachable[vert] = true;
+        let mut verts = vec![vert];
+        while let Some(vert) = verts.pop() {
+            for &arc in &self.out_arcs[vert] {
+                let head = self.heads[arc];
+                if !reachable[head] && self.capacities[arc] > C::zero() {
+                    reachable[head] = true;
+                    verts.push(head);
+                }
+            }
+        }
+        reachable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reuse() {
+        let mut network: FlowNetwork = FlowNetwork::new(&[
+            Edge { from: 1, to: 2, capacity: 2 },
+            Edge { from: 2, to: 3, capacity: 5 },
+        ]);
+
+        assert_eq!(network.max_flow(1, 3), 2);
+        assert_eq!(network.max_flow(1, 3), 0);
+        assert_eq!(network.flow(1), 2);
+
+        let edge = network.add_edge(1, 3, 4);
+        assert_eq!(network.max_flow(1, 3), 4);
+        assert_eq!(network.flow(edge), 4);
+
+        network.reset();
+        assert_eq!(network.flow(1), 0);
+        assert_eq!(network.max_flow(2, 3), 5);
+        assert_eq!(network.max_flow(4, 3), 0);
+    }
+
+    #[test]
+    fn test_undirected_reset() {
+        let mut network: FlowNetwork = FlowNetwork::new(&[]);
+        network.add_undirected_edge(1, 2, 4);
+
+        assert_eq!(network.max_flow(2, 1), 4);
+        assert_eq!(network.directed_flow(0), (2, 1, 4));
+        network.reset();
+        assert_eq!(network.directed_flow(0), (1, 2, 0));
+        assert_eq!(network.max_flow(1, 2), 4);
+        assert_eq!(network.directed_flow(0), (1, 2, 4));
+    }
+
+    #[test]
+    fn test_undirected_near_max_capacity() {
+        let mut network: FlowNetwork = FlowNetwork::new(&[]);
+        network.add_undirected_edge(1, 2, 65_535);
+
+        assert_eq!(network.max_flow(1, 2), 65_535);
+        assert_eq!(network.directed_flow(0), (1, 2, 65_535));
+        // the flow is taken back, then the full capacity goes the other way
+        assert_eq!(network.max_flow(2, 1), 131_070);
+        assert_eq!(network.directed_flow(0), (2, 1, 65_535));
+    }
+}
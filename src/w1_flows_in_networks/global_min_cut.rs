@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::VertId;
+use w1_flows_in_networks::add_capacities;
+
+#[derive(Debug)]
+pub struct GlobalMinCut<C> {
+    /// total capacity of edges between the sides
+    pub capacity: C,
+    pub side: HashSet<VertId>,
+    pub other_side: HashSet<VertId>,
+}
+
+/// Stoer-Wagner: minimum cut over all pairs of vertices of the graph with every edge read as undirected.
+/// Each phase orders vertices by how strongly they are connected to the ones before them,
+/// the last vertex alone is a minimum cut between the last two, which are then merged.
+/// Weights of merged vertices add up, so they and the result are of the wider `C::Sum` type.
+pub fn global_min_cut<C: Capacity>(edges: &[Edge<C>]) -> GlobalMinCut<C::Sum> {
+    let mut verts: Vec<VertId> = edges.iter().flat_map(|edge| vec![edge.from, edge.to]).collect();
+    verts.sort();
+    verts.dedup();
+    assert!(verts.len() >= 2, "a cut needs at least two vertices, got {:?}", verts);
+
+    let verts_count = verts.len();
+    let mut weights = vec![vec![C::Sum::zero(); verts_count]; verts_count];
+    for edge in edges {
+        if edge.from != edge.to && edge.capacity > C::zero() {
+            let from = verts.binary_search(&edge.from).unwrap();
+            let to = verts.binary_search(&edge.to).unwrap();
+            weights[from][to] = add_capacities(weights[from][to], edge.capacity.to_sum());
+            weights[to][from] = weights[from][to];
+        }
+    }
+    // vertex -> original vertices merged into it
+    let mut merged: Vec<Vec<VertId>> = verts.iter().map(|&vert| vec![vert]).collect();
+    let mut active: Vec<usize> = (0..verts_count).collect();
+
+    let mut best: Option<C::Sum> = None;
+    let mut best_side = Vec::new();
+    while active.len() > 1 {
+        let mut connections = vec![C::Sum::zero(); verts_count];
+        let mut added = vec![false; verts_count];
+        let mut prev = active[0];
+        let mut last = active[0];
+        for _ in 0..active.len() {
+            let next = *active.iter()
+                .filter(|&&vert| !added[vert])
+                .fold(None, |strongest: Option<&usize>, vert| match strongest {
+                    Some(strongest) if connections[*strongest] >= connections[*vert] => Some(strongest),
+                    _ => Some(vert),
+                })
+                .unwrap();
+            added[next] = true;
+            prev = last;
+            last = next;
+            for &vert in &active {
+                if !added[vert] {
+                    connections[vert] = add_capacities(connections[vert], weights[next][vert]);
+                }
+            }
+        }
+
+        let cut = connections[last];
+        if best.is_none_or(|capacity| cut < capacity) {
+            best = Some(cut);
+            best_side = merged[last].clone();
+        }
+
+        let last_merged = merged[last].split_off(0);
+        merged[prev].extend(last_merged);
+        for &vert in &active {
+            if vert != prev && vert != last {
+                weights[prev][vert] = add_capacities(weights[prev][vert], weights[last][vert]);
+                weights[vert][prev] = weights[prev][vert];
+            }
+        }
+        active.retain(|&vert| vert != last);
+    }
+
+    let side: HashSet<VertId> = best_side.into_iter().collect();
+    let other_side = verts.iter().cloned().filter(|vert| !side.contains(vert)).collect();
+    GlobalMinCut { capacity: best.unwrap(), side, other_side }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use w1_flows_in_networks::gomory_hu::GomoryHuTree;
+    use w1_flows_in_networks::test_utils::gen_edges;
+
+    #[test]
+    fn test1() {
+        let graph: Vec<Edge> = vec![
+            Edge { from: 1, to: 2, capacity: 2 },
+            Edge { from: 1, to: 5, capacity: 3 },
+            Edge { from: 2, to: 3, capacity: 3 },
+            Edge { from: 2, to: 5, capacity: 2 },
+            Edge { from: 2, to: 6, capacity: 2 },
+            Edge { from: 3, to: 4, capacity: 4 },
+            Edge { from: 3, to: 7, capacity: 2 },
+            Edge { from: 4, to: 7, capacity: 2 },
+            Edge { from: 4, to: 8, capacity: 2 },
+            Edge { from: 5, to: 6, capacity: 3 },
+            Edge { from: 6, to: 7, capacity: 1 },
+            Edge { from: 7, to: 8, capacity: 3 },
+        ];
+
+        let min_cut = global_min_cut(&graph);
+
+        assert_eq!(min_cut.capacity, 4);
+        let sides = [min_cut.side, min_cut.other_side];
+        assert!(sides.contains(&[3, 4, 7, 8].iter().cloned().collect()));
+        assert!(sides.contains(&[1, 2, 5, 6].iter().cloned().collect()));
+    }
+
+    #[test]
+    fn test_disconnected() {
+        let graph: Vec<Edge> = vec![
+            Edge { from: 1, to: 2, capacity: 5 },
+            Edge { from: 3, to: 4, capacity: 5 },
+        ];
+
+        let min_cut = global_min_cut(&graph);
+
+        assert_eq!(min_cut.capacity, 0);
+        assert_eq!(min_cut.side.len() + min_cut.other_side.len(), 4);
+    }
+
+    #[test]
+    fn test_capacity_wider_than_edges() {
+        let graph = vec![
+            Edge { from: 1, to: 2, capacity: 60_000u16 },
+            Edge { from: 1, to: 2, capacity: 60_000 },
+            Edge { from: 2, to: 3, capacity: 60_000 },
+            Edge { from: 3, to: 1, capacity: 60_000 },
+        ];
+
+        assert_eq!(global_min_cut(&graph).capacity, 120_000u32);
+    }
+
+    #[test]
+    fn test_rnd() {
+        for _ in 0..1000 {
+            let edges = gen_edges(10, 30, 10);
+            if edges.iter().all(|edge| edge.from == edge.to) {
+                continue;
+            }
+
+            let min_cut = global_min_cut(&edges);
+
+            let expected = GomoryHuTree::new(&edges).edges().iter().map(|edge| edge.capacity).min().unwrap();
+            assert_eq!(min_cut.capacity, expected, "edges {:?}", edges);
+            assert!(!min_cut.side.is_empty() && !min_cut.other_side.is_empty());
+            let cut_capacity: u64 = edges.iter()
+                .filter(|edge| min_cut.side.contains(&edge.from) != min_cut.side.contains(&edge.to))
+                .map(|edge| u64::from(edge.capacity))
+                .sum();
+            assert_eq!(cut_capacity, min_cut.capacity, "edges {:?}", edges);
+        }
+    }
+}
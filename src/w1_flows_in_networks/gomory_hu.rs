@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::FlowNetwork;
+use w1_flows_in_networks::VertId;
+
+/// Tree on the vertices of an undirected graph such that the minimum cut between any two vertices
+/// is the lightest edge on the tree path between them, and removing that edge splits the vertices
+/// into the sides of the cut.
+#[derive(Debug)]
+pub struct GomoryHuTree<C: Capacity = u16> {
+    verts: Vec<VertId>,
+    vert_to_i: HashMap<VertId, usize>,
+    /// tree parent of every vertex by index, the first vertex is the root and its own parent
+    parents: Vec<usize>,
+    /// weight of the edge from a vertex to its parent
+    weights: Vec<C::Sum>,
+}
+
+impl<C: Capacity> GomoryHuTree<C> {
+    /// Builds the tree with n-1 maximum flows by Gusfield's algorithm, which needs no vertex contraction.
+    /// Every edge is read as undirected.
+    pub fn new(edges: &[Edge<C>]) -> GomoryHuTree<C> {
+        let mut verts: Vec<VertId> = edges.iter().flat_map(|edge| vec![edge.from, edge.to]).collect();
+        verts.sort();
+        verts.dedup();
+        let vert_to_i: HashMap<VertId, usize> = verts.iter().enumerate().map(|(i, &vert)| (vert, i)).collect();
+
+        let mut network = FlowNetwork::new(&[]);
+        for edge in edges {
+            network.add_edge(edge.from, edge.to, edge.capacity);
+            network.add_edge(edge.to, edge.from, edge.capacity);
+        }
+
+        let mut parents = vec![0; verts.len()];
+        let mut weights = vec![C::Sum::zero(); verts.len()];
+        for s in 1..verts.len() {
+            let t = parents[s];
+            network.reset();
+            let amount = network.max_flow(verts[s], verts[t]);
+            let reachable = network.reachable_from(verts[s] as usize);
+            let in_cut = |i: usize| reachable[verts[i] as usize];
+
+            weights[s] = amount;
+            for (i, parent) in parents.iter_mut().enumerate() {
+                if i != s && in_cut(i) && *parent == t {
+                    *parent = s;
+                }
+            }
+            if in_cut(parents[t]) {
+                parents[s] = parents[t];
+                parents[t] = s;
+                weights[s] = weights[t];
+                weights[t] = amount;
+            }
+        }
+
+        GomoryHuTree { verts, vert_to_i, parents, weights }
+    }
+
+    /// Tree edges, from a vertex to its parent
+    pub fn edges(&self) -> Vec<Edge<C::Sum>> {
+        (1..self.verts.len())
+            .map(|i| Edge { from: self.verts[i], to: self.verts[self.parents[i]], capacity: self.weights[i] })
+            .collect()
+    }
+
+    pub fn min_cut_value(&self, a: VertId, b: VertId) -> C::Sum {
+        self.weights[self.lightest_edge(a, b)]
+    }
+
+    /// Sides of a minimum cut between the vertices, the first one contains `a`
+    pub fn min_cut(&self, a: VertId, b: VertId) -> (HashSet<VertId>, HashSet<VertId>) {
+        let child = self.lightest_edge(a, b);
+        let (subtree, rest): (Vec<usize>, Vec<usize>) = (0..self.verts.len())
+            .partition(|&i| self.ancestors(i).contains(&child));
+        let subtree: HashSet<VertId> = subtree.iter().map(|&i| self.verts[i]).collect();
+        let rest: HashSet<VertId> = rest.iter().map(|&i| self.verts[i]).collect();
+        if subtree.contains(&a) {
+            (subtree, rest)
+        } else {
+            (rest, subtree)
+        }
+    }
+
+    /// Child end of the lightest edge on the tree path between the vertices
+    fn lightest_edge(&self, a: VertId, b: VertId) -> usize {
+        assert!(a != b, "no cut between vertex {} and itself", a);
+        let a_ancestors = self.ancestors(self.index(a));
+        let b_ancestors = self.ancestors(self.index(b));
+        let b_ancestors_set: HashSet<usize> = b_ancestors.iter().cloned().collect();
+        let lca = *a_ancestors.iter().find(|i| b_ancestors_set.contains(i)).unwrap();
+
+        a_ancestors.iter().take_while(|&&i| i != lca)
+            .chain(b_ancestors.iter().take_while(|&&i| i != lca))
+            .fold(None, |lightest: Option<usize>, &i| match lightest {
+                Some(lightest) if self.weights[lightest] <= self.weights[i] => Some(lightest),
+                _ => Some(i),
+            })
+            .unwrap()
+    }
+
+    /// The vertex, its parent, and so on up to the root
+    fn ancestors(&self, mut i: usize) -> Vec<usize> {
+        let mut ancestors = vec![i];
+        while self.parents[i] != i {
+            i = self.parents[i];
+            ancestors.push(i);
+        }
+        ancestors
+    }
+
+    fn index(&self, vert: VertId) -> usize {
+        *self.vert_to_i.get(&vert).unwrap_or_else(|| panic!("vertex {} is not in the graph", vert))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use w1_flows_in_networks::test_utils::gen_edges;
+
+    #[test]
+    fn test1() {
+        let graph: Vec<Edge> = vec![
+            Edge { from: 1, to: 2, capacity: 1 },
+            Edge { from: 1, to: 3, capacity: 7 },
+            Edge { from: 2, to: 3, capacity: 1 },
+            Edge { from: 2, to: 4, capacity: 3 },
+            Edge { from: 2, to: 5, capacity: 2 },
+            Edge { from: 3, to: 5, capacity: 4 },
+            Edge { from: 4, to: 5, capacity: 1 },
+            Edge { from: 4, to: 6, capacity: 6 },
+            Edge { from: 5, to: 6, capacity: 2 },
+        ];
+
+        let tree = GomoryHuTree::new(&graph);
+
+        assert_eq!(tree.edges().len(), 5);
+        assert_eq!(tree.min_cut_value(1, 3), 8);
+        assert_eq!(tree.min_cut_value(1, 6), 6);
+        assert_eq!(tree.min_cut_value(4, 6), 8);
+        let (one_side, other_side) = tree.min_cut(6, 1);
+        assert_eq!(one_side, [4, 6].iter().cloned().collect());
+        assert_eq!(other_side, [1, 2, 3, 5].iter().cloned().collect());
+    }
+
+    #[test]
+    fn test_rnd() {
+        for _ in 0..300 {
+            let edges = gen_edges(8, 20, 10);
+
+            let tree = GomoryHuTree::new(&edges);
+
+            let mut undirected = FlowNetwork::new(&[]);
+            for edge in &edges {
+                undirected.add_edge(edge.from, edge.to, edge.capacity);
+                undirected.add_edge(edge.to, edge.from, edge.capacity);
+            }
+            for &a in &tree.verts {
+                for &b in &tree.verts {
+                    if a == b {
+                        continue;
+                    }
+                    undirected.reset();
+                    let expected = undirected.max_flow(a, b);
+                    assert_eq!(tree.min_cut_value(a, b), expected, "{} - {}, edges {:?}", a, b, edges);
+
+                    let (a_side, b_side) = tree.min_cut(a, b);
+                    assert!(a_side.contains(&a) && b_side.contains(&b));
+                    assert_eq!(a_side.len() + b_side.len(), tree.verts.len());
+                    let cut_capacity: u32 = edges.iter()
+                        .filter(|edge| a_side.contains(&edge.from) != a_side.contains(&edge.to))
+                        .map(|edge| edge.capacity)
+                        .sum();
+                    assert_eq!(cut_capacity as u64, expected, "{} - {}, edges {:?}", a, b, edges);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use w1_flows_in_networks::VertId;
+
+const NONE: usize = usize::MAX;
+
+/// Maximum matching of a bipartite graph given as left vertex -> right vertices it can be matched with.
+/// Hopcroft-Karp: every phase finds shortest augmenting paths by BFS from all free left vertices
+/// and augments along a maximal set of vertex-disjoint ones, which takes O(√V) phases of O(E).
+pub fn max_matching(left_to_rights: &HashMap<VertId, Vec<VertId>>) -> HashMap<VertId, VertId> {
+    let mut lefts: Vec<VertId> = left_to_rights.keys().cloned().collect();
+    lefts.sort();
+    let mut rights: Vec<VertId> = left_to_rights.values().flat_map(|rights| rights.iter().cloned()).collect();
+    rights.sort();
+    rights.dedup();
+    let adjacent: Vec<Vec<usize>> = lefts.iter()
+        .map(|left| left_to_rights[left].iter().map(|right| rights.binary_search(right).unwrap()).collect())
+        .collect();
+
+    let mut left_matches = vec![NONE; lefts.len()];
+    let mut right_matches = vec![NONE; rights.len()];
+    while let Some((dists, shortest)) = layers(&adjacent, &left_matches, &right_matches) {
+        augment(&adjacent, dists, shortest, &mut left_matches, &mut right_matches);
+    }
+
+    left_matches.iter().enumerate()
+        .filter(|&(_, &right)| right != NONE)
+        .map(|(left, &right)| (lefts[left], rights[right]))
+        .collect()
+}
+
+/// Distances of left vertices from free left vertices along alternating paths up to the first layer
+/// adjacent to a free right vertex and the distance of that layer, `None` if no augmenting path is left
+fn layers(adjacent: &[Vec<usize>], left_matches: &[usize], right_matches: &[usize])
+          -> Option<(Vec<usize>, usize)> {
+    let mut dists = vec![NONE; adjacent.len()];
+    let mut lefts = VecDeque::new();
+    for (left, &right) in left_matches.iter().enumerate() {
+        if right == NONE {
+            dists[left] = 0;
+            lefts.push_back(left);
+        }
+    }
+
+    let mut shortest = NONE;
+    while let Some(left) = lefts.pop_front() {
+        if dists[left] > shortest {
+            break;
+        }
+        for &right in &adjacent[left] {
+            let next_left = right_matches[right];
+            if next_left == NONE {
+                shortest = dists[left];
+            } else if dists[next_left] == NONE {
+                dists[next_left] = dists[left] + 1;
+                lefts.push_back(next_left);
+            }
+        }
+    }
+    if shortest == NONE { None } else { Some((dists, shortest)) }
+}
+
+/// Augments along vertex-disjoint shortest paths from every free left vertex,
+/// which reach a free right vertex from a left one at distance `shortest`.
+/// Depth first search is iterative as paths can be as long as the number of vertices.
+fn augment(adjacent: &[Vec<usize>], mut dists: Vec<usize>, shortest: usize,
+           left_matches: &mut [usize], right_matches: &mut [usize]) {
+    // left vertex -> position of the next right vertex to try
+    let mut next = vec![0; adjacent.len()];
+    for start in 0..adjacent.len() {
+        if left_matches[start] != NONE {
+            continue;
+        }
+        let mut path = vec![start];
+        while let Some(&left) = path.last() {
+            if next[left] == adjacent[left].len() {
+                // dead end, do not come here again in this phase
+                dists[left] = NONE;
+                path.pop();
+                if let Some(&prev) = path.last() {
+                    next[prev] += 1;
+                }
+                continue;
+            }
+
+            let right = adjacent[left][next[left]];
+            let next_left = right_matches[right];
+            if next_left == NONE && dists[left] == shortest {
+                for &left in &path {
+                    let right = adjacent[left][next[left]];
+                    left_matches[left] = right;
+                    right_matches[right] = left;
+                }
+                break;
+            } else if next_left != NONE && dists[left] < shortest && dists[next_left] == dists[left] + 1 {
+                path.push(next_left);
+            } else {
+                next[left] += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::thread_rng;
+    use w1_flows_in_networks::Edge;
+    use w1_flows_in_networks::FlowNetwork;
+
+    #[test]
+    fn test1() {
+        let mut left_to_rights = HashMap::new();
+        left_to_rights.insert(1, vec![1, 2]);
+        left_to_rights.insert(2, vec![1]);
+        left_to_rights.insert(3, vec![2, 3]);
+        left_to_rights.insert(4, vec![3, 4]);
+
+        let matching = max_matching(&left_to_rights);
+
+        assert_eq!(matching, [(1, 2), (2, 1), (3, 3), (4, 4)].iter().cloned().collect());
+    }
+
+    #[test]
+    fn test_long_path() {
+        // greedy matching of every left to the next right leaves one augmenting path through all vertices
+        let verts_count = 20_000;
+        let mut left_to_rights = HashMap::new();
+        for left in 1..verts_count + 1 {
+            left_to_rights.insert(left, if left == verts_count { vec![left] } else { vec![left + 1, left] });
+        }
+
+        assert_eq!(max_matching(&left_to_rights).len(), verts_count as usize);
+    }
+
+    #[test]
+    fn test_shortest_paths_only() {
+        // free left 0 is next to free right 1, the longer path through left 1 to free right 2 is left alone
+        let adjacent = vec![vec![0, 1], vec![0, 2]];
+        let mut left_matches = vec![NONE, 0];
+        let mut right_matches = vec![1, NONE, NONE];
+
+        let (dists, shortest) = layers(&adjacent, &left_matches, &right_matches).unwrap();
+        assert_eq!(shortest, 0);
+        augment(&adjacent, dists, shortest, &mut left_matches, &mut right_matches);
+
+        assert_eq!(left_matches, [1, 0]);
+        assert_eq!(right_matches, [1, 0, NONE]);
+    }
+
+    #[test]
+    fn test_rnd() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let mut left_to_rights: HashMap<VertId, Vec<VertId>> = HashMap::new();
+            for left in 1..rng.gen_range(1, 11) {
+                let rights = (0..rng.gen_range(0, 5)).map(|_| rng.gen_range(1, 11)).collect();
+                left_to_rights.insert(left, rights);
+            }
+
+            let matching = max_matching(&left_to_rights);
+
+            let mut matched_rights = Vec::new();
+            for (left, right) in &matching {
+                assert!(left_to_rights[left].contains(right));
+                assert!(!matched_rights.contains(right), "right {} is matched twice, graph {:?}", right, left_to_rights);
+                matched_rights.push(*right);
+            }
+
+            // lefts are 1..=10, rights are 11..=20, source 0 and sink 21
+            let mut edges = Vec::new();
+            for (&left, rights) in &left_to_rights {
+                edges.push(Edge { from: 0, to: left, capacity: 1 });
+                for &right in rights {
+                    edges.push(Edge { from: left, to: right + 10, capacity: 1 });
+                }
+            }
+            for right in 11..21 {
+                edges.push(Edge { from: right, to: 21, capacity: 1 });
+            }
+            let expected = FlowNetwork::new(&edges).max_flow(0, 21);
+            assert_eq!(matching.len(), expected as usize, "graph {:?}", left_to_rights);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use w1_flows_in_networks::VertId;
+
+const INF: i64 = i64::MAX;
+
+#[derive(Debug)]
+pub struct MinCostMatching {
+    /// left vertex -> right vertex
+    pub matching: HashMap<VertId, VertId>,
+    /// sum of costs of matched pairs
+    pub cost: i64,
+}
+
+/// Matching of maximum size, and of minimum total cost among those, of a bipartite graph given as
+/// left vertex -> right vertex it can be matched with -> cost. Costs may be negative.
+/// Hungarian algorithm with rows on the smaller side, where pairs that can not be matched cost 0
+/// and every allowed pair gets a discount bigger than any difference in costs, so more pairs are always cheaper.
+/// Takes O(n^2 * m) time for n vertices on the smaller side and m on the larger one, and O(E) memory,
+/// use `min_cost_flow::min_cost_max_flow` for graphs too large for that.
+pub fn min_cost_matching(left_to_right_costs: &HashMap<VertId, HashMap<VertId, i64>>) -> MinCostMatching {
+    let mut lefts: Vec<VertId> = left_to_right_costs.keys().cloned().collect();
+    lefts.sort();
+    let mut rights: Vec<VertId> = left_to_right_costs.values().flat_map(|costs| costs.keys().cloned()).collect();
+    rights.sort();
+    rights.dedup();
+
+    // row -> column -> cost by indices, rows are the smaller side
+    let rows_are_lefts = lefts.len() <= rights.len();
+    let (rows_count, columns_count) = if rows_are_lefts { (lefts.len(), rights.len()) } else { (rights.len(), lefts.len()) };
+    let mut row_costs: Vec<HashMap<usize, i64>> = vec![HashMap::new(); rows_count];
+    for (left_i, left) in lefts.iter().enumerate() {
+        for (right, &cost) in &left_to_right_costs[left] {
+            let right_i = rights.binary_search(right).unwrap();
+            let (row, column) = if rows_are_lefts { (left_i, right_i) } else { (right_i, left_i) };
+            row_costs[row].insert(column, cost);
+        }
+    }
+
+    let costs = || left_to_right_costs.values().flat_map(|costs| costs.values().cloned());
+    let min_cost = costs().min().unwrap_or(0);
+    let max_cost = costs().max().unwrap_or(0);
+    // potentials add up at most rows_count + 1 discounted costs, they must fit too
+    let discount = (max_cost as i128 - min_cost as i128) * rows_count as i128
+        + (min_cost as i128).abs() + (max_cost as i128).abs() + 1;
+    let largest = (discount + (min_cost as i128).abs() + (max_cost as i128).abs()) * (rows_count as i128 + 1) * 2;
+    assert!(largest <= i64::MAX as i128, "costs from {} to {} are too far apart for {} pairs", min_cost, max_cost, rows_count);
+    let discount = discount as i64;
+
+    let cost = |row: usize, column: usize| row_costs[row].get(&column).map_or(0, |&cost| cost - discount);
+    let column_rows = assign(rows_count, columns_count, cost);
+
+    let mut matching = HashMap::new();
+    let mut cost = 0i64;
+    for (column, &row) in column_rows.iter().enumerate().skip(1) {
+        if row == 0 || !row_costs[row - 1].contains_key(&(column - 1)) {
+            continue;
+        }
+        let (left_i, right_i) = if rows_are_lefts { (row - 1, column - 1) } else { (column - 1, row - 1) };
+        let (left, right) = (lefts[left_i], rights[right_i]);
+        let pair_cost = left_to_right_costs[&left][&right];
+        matching.insert(left, right);
+        cost = cost.checked_add(pair_cost).unwrap_or_else(|| panic!("cost overflow: {} + {}", cost, pair_cost));
+    }
+    MinCostMatching { matching, cost }
+}
+
+/// Rows are added one by one, each along the cheapest augmenting path with respect to potentials of rows and columns.
+/// Every row gets a column, so there must be at least as many columns as rows.
+/// Returns column -> its row, both 1-based, 0 for a column without a row.
+fn assign<F: Fn(usize, usize) -> i64>(rows_count: usize, columns_count: usize, cost: F) -> Vec<usize> {
+    let mut row_potentials = vec![0i64; rows_count + 1];
+    let mut column_potentials = vec![0i64; columns_count + 1];
+    let mut column_rows = vec![0; columns_count + 1];
+    // column -> previous column on the augmenting path
+    let mut prev_columns = vec![0; columns_count + 1];
+
+    for row in 1..rows_count + 1 {
+        column_rows[0] = row;
+        let mut column = 0;
+        let mut min_reduced = vec![INF; columns_count + 1];
+        let mut used = vec![false; columns_count + 1];
+        loop {
+            used[column] = true;
+            let column_row = column_rows[column];
+            let mut delta = INF;
+            let mut next_column = 0;
+            for j in 1..columns_count + 1 {
+                if !used[j] {
+                    let reduced = cost(column_row - 1, j - 1) - row_potentials[column_row] - column_potentials[j];
+                    if reduced < min_reduced[j] {
+                        min_reduced[j] = reduced;
+                        prev_columns[j] = column;
+                    }
+                    if min_reduced[j] < delta {
+                        delta = min_reduced[j];
+                        next_column = j;
+                    }
+                }
+            }
+            for j in 0..columns_count + 1 {
+                if used[j] {
+                    row_potentials[column_rows[j]] += delta;
+                    column_potentials[j] -= delta;
+                } else {
+                    min_reduced[j] -= delta;
+                }
+            }
+            column = next_column;
+            if column_rows[column] == 0 {
+                break;
+            }
+        }
+        while column != 0 {
+            let prev_column = prev_columns[column];
+            column_rows[column] = column_rows[prev_column];
+            column = prev_column;
+        }
+    }
+    column_rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::thread_rng;
+    use w1_flows_in_networks::CostEdge;
+    use w1_flows_in_networks::min_cost_flow::min_cost_max_flow;
+    use w1_flows_in_networks::test_utils::flow_amount;
+
+    #[test]
+    fn test1() {
+        let mut costs: HashMap<VertId, HashMap<VertId, i64>> = HashMap::new();
+        costs.entry(1).or_default().insert(1, 4);
+        costs.entry(1).or_default().insert(2, 1);
+        costs.entry(2).or_default().insert(1, 2);
+        costs.entry(2).or_default().insert(2, 8);
+        costs.entry(3).or_default().insert(2, 3);
+
+        let min_cost_matching = min_cost_matching(&costs);
+
+        assert_eq!(min_cost_matching.matching, [(1, 2), (2, 1)].iter().cloned().collect());
+        assert_eq!(min_cost_matching.cost, 3);
+    }
+
+    #[test]
+    fn test_size_before_cost() {
+        let mut costs: HashMap<VertId, HashMap<VertId, i64>> = HashMap::new();
+        costs.entry(1).or_default().insert(1, -100);
+        costs.entry(1).or_default().insert(2, 50);
+        costs.entry(2).or_default().insert(1, 50);
+
+        let min_cost_matching = min_cost_matching(&costs);
+
+        assert_eq!(min_cost_matching.matching, [(1, 2), (2, 1)].iter().cloned().collect());
+        assert_eq!(min_cost_matching.cost, 100);
+    }
+
+    #[test]
+    fn test_more_lefts_than_rights() {
+        let mut costs: HashMap<VertId, HashMap<VertId, i64>> = HashMap::new();
+        costs.entry(1).or_default().insert(7, 5);
+        costs.entry(2).or_default().insert(7, 2);
+        costs.entry(3).or_default().insert(7, 9);
+        costs.entry(3).or_default().insert(8, 1);
+
+        let min_cost_matching = min_cost_matching(&costs);
+
+        assert_eq!(min_cost_matching.matching, [(2, 7), (3, 8)].iter().cloned().collect());
+        assert_eq!(min_cost_matching.cost, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "too far apart")]
+    fn test_cost_range_overflow() {
+        let mut costs: HashMap<VertId, HashMap<VertId, i64>> = HashMap::new();
+        costs.entry(1).or_default().insert(1, i64::MIN);
+        costs.entry(2).or_default().insert(2, i64::MAX);
+
+        min_cost_matching(&costs);
+    }
+
+    #[test]
+    fn test_rnd() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let mut costs: HashMap<VertId, HashMap<VertId, i64>> = HashMap::new();
+            for left in 1..rng.gen_range(1, 8) {
+                for _ in 0..rng.gen_range(0, 4) {
+                    costs.entry(left).or_default().insert(rng.gen_range(1, 8), rng.gen_range(-10, 20));
+                }
+            }
+
+            let min_cost_matching = min_cost_matching(&costs);
+
+            // lefts are 1..=7, rights are 11..=17, source 0 and sink 20
+            let mut edges = Vec::new();
+            for (&left, right_costs) in &costs {
+                edges.push(CostEdge { from: 0, to: left, capacity: 1u32, cost: 0 });
+                for (&right, &cost) in right_costs {
+                    edges.push(CostEdge { from: left, to: right + 10, capacity: 1, cost });
+                }
+            }
+            for right in 11..18 {
+                edges.push(CostEdge { from: right, to: 20, capacity: 1, cost: 0 });
+            }
+            let expected = min_cost_max_flow(&edges, 0, 20);
+
+            assert_eq!(min_cost_matching.matching.len(), flow_amount(&expected.flows, 0) as usize, "costs {:?}", costs);
+            assert_eq!(min_cost_matching.cost, expected.cost, "costs {:?}", costs);
+            let mut matched_rights: Vec<&VertId> = min_cost_matching.matching.values().collect();
+            matched_rights.sort();
+            matched_rights.dedup();
+            assert_eq!(matched_rights.len(), min_cost_matching.matching.len());
+        }
+    }
+}
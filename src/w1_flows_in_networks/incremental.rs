@@ -0,0 +1,161 @@
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::FlowNetwork;
+use w1_flows_in_networks::VertId;
+use w1_flows_in_networks::add_capacities;
+use w1_flows_in_networks::min_capacity;
+use w1_flows_in_networks::shortest_path;
+use w1_flows_in_networks::sub_capacities;
+
+/// Keeps a maximum flow up to date while edges are added and capacities change,
+/// starting every update from the current flow instead of from scratch.
+#[derive(Debug)]
+pub struct IncrementalMaxFlow<C: Capacity = u16> {
+    network: FlowNetwork<C>,
+    from: VertId,
+    to: VertId,
+    amount: C::Sum,
+}
+
+impl<C: Capacity> IncrementalMaxFlow<C> {
+    pub fn new(edges: &[Edge<C>], from: VertId, to: VertId) -> IncrementalMaxFlow<C> {
+        let mut network = FlowNetwork::new(edges);
+        let amount = network.max_flow(from, to);
+        IncrementalMaxFlow { network, from, to, amount }
+    }
+
+    pub fn amount(&self) -> C::Sum {
+        self.amount
+    }
+
+    /// Edge indices follow the input slice, then the order of `add_edge` calls
+    pub fn network(&self) -> &FlowNetwork<C> {
+        &self.network
+    }
+
+    /// Returns index of the new edge
+    pub fn add_edge(&mut self, from: VertId, to: VertId, capacity: C) -> usize {
+        let edge = self.network.add_edge(from, to, capacity);
+        self.augment();
+        edge
+    }
+
+    pub fn set_capacity(&mut self, edge: usize, capacity: C) {
+        let capacity = if capacity > C::zero() { capacity } else { C::zero() };
+        let arc = 2 * edge;
+        let flow = self.network.flow(edge);
+        if capacity >= flow {
+            self.network.capacities[arc] = sub_capacities(capacity, flow);
+            self.augment();
+            return;
+        }
+
+        // the edge can not keep its flow: its tail gets more than it sends, its head sends more than it gets
+        let excess = sub_capacities(flow, capacity);
+        self.network.capacities[arc] = C::zero();
+        self.network.capacities[arc + 1] = capacity;
+        let tail = self.network.tail(arc);
+        let head = self.network.heads[arc];
+
+        // first try to route the excess around the edge, then return the rest to the source and take it from the sink
+        let rerouted = push_up_to(&mut self.network, tail, head, excess);
+        let not_rerouted = sub_capacities(excess, rerouted);
+        if not_rerouted > C::zero() {
+            let source = self.from as usize;
+            let sink = self.to as usize;
+            if tail != source {
+                let returned = push_up_to(&mut self.network, tail, source, not_rerouted);
+                assert!(returned == not_rerouted, "can not return {:?} to the source, returned {:?}", not_rerouted, returned);
+            }
+            if head != sink {
+                let taken = push_up_to(&mut self.network, sink, head, not_rerouted);
+                assert!(taken == not_rerouted, "can not take {:?} from the sink, taken {:?}", not_rerouted, taken);
+            }
+            self.amount = sub_capacities(self.amount, not_rerouted.to_sum());
+        }
+        self.augment();
+    }
+
+    fn augment(&mut self) {
+        let added = self.network.max_flow(self.from, self.to);
+        self.amount = add_capacities(self.amount, added);
+    }
+}
+
+/// Pushes at most `limit` along shortest residual paths, returns the amount pushed
+fn push_up_to<C: Capacity>(network: &mut FlowNetwork<C>, from: usize, to: usize, limit: C) -> C {
+    let mut pushed = C::zero();
+    while pushed < limit {
+        match shortest_path(network, from, to) {
+            Some(path) => {
+                let left = sub_capacities(limit, pushed);
+                let path_capacity = min_capacity(network, &path);
+                let amount = if path_capacity < left { path_capacity } else { left };
+                for &arc in &path {
+                    network.push(arc, amount);
+                }
+                pushed = add_capacities(pushed, amount);
+            }
+            None => break,
+        }
+    }
+    pushed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::thread_rng;
+    use w1_flows_in_networks::test_utils::check_flows;
+    use w1_flows_in_networks::test_utils::evacuation_graph;
+    use w1_flows_in_networks::test_utils::gen_edges;
+
+    #[test]
+    fn test1() {
+        let graph = evacuation_graph();
+        let mut max_flow = IncrementalMaxFlow::new(&graph, 1, 5);
+        assert_eq!(max_flow.amount(), 6);
+
+        // widen the road into the shelter
+        max_flow.set_capacity(4, 3);
+        assert_eq!(max_flow.amount(), 7);
+
+        let edge = max_flow.add_edge(3, 5, 10);
+        assert_eq!(max_flow.amount(), 8);
+        assert_eq!(max_flow.network().flow(edge), 1);
+
+        // close the main road
+        max_flow.set_capacity(1, 0);
+        assert_eq!(max_flow.amount(), 7);
+        assert_eq!(max_flow.network().flow(1), 0);
+    }
+
+    #[test]
+    fn test_rnd() {
+        let mut rng = thread_rng();
+        for _ in 0..300 {
+            let mut edges = gen_edges(10, 30, 10);
+            let mut max_flow = IncrementalMaxFlow::new(&edges, 1, 2);
+
+            for _ in 0..20 {
+                if edges.is_empty() || rng.gen_range(0, 4) == 0 {
+                    let edge = Edge { from: rng.gen_range(1, 11), to: rng.gen_range(1, 11), capacity: rng.gen_range(0, 11) };
+                    assert_eq!(max_flow.add_edge(edge.from, edge.to, edge.capacity), edges.len());
+                    edges.push(edge);
+                } else {
+                    let edge = rng.gen_range(0, edges.len());
+                    let capacity = rng.gen_range(0, 11);
+                    max_flow.set_capacity(edge, capacity);
+                    edges[edge].capacity = capacity;
+                }
+
+                check_flows(&edges, &max_flow.network().flows(), 1, 2);
+                assert_eq!(max_flow.amount(), FlowNetwork::new(&edges).max_flow(1, 2), "edges {:?}", edges);
+                for (i, edge) in edges.iter().enumerate() {
+                    assert!(max_flow.network().flow(i) <= edge.capacity);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::CostEdge;
+use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::Flows;
+use w1_flows_in_networks::VertId;
+use w1_flows_in_networks::FlowNetwork;
+use w1_flows_in_networks::min_capacity;
+
+const NO_DIST: i64 = i64::MAX;
+const NO_ARC: usize = usize::MAX;
+
+#[derive(Debug)]
+pub struct MinCostFlow<C> {
+    /// parallel edges are merged, so amounts are of the wider type
+    pub flows: Flows<C>,
+    /// sum of flow * cost over all edges
+    pub cost: i64,
+}
+
+/// Maximum flow of minimum total cost, found by successive shortest paths with potentials.
+/// Costs may be negative as long as there is no cycle of negative cost.
+/// Panics if the flow on an edge does not convert to a whole `i64`, e.g. a fractional `f64` flow.
+pub fn min_cost_max_flow<C: Capacity>(edges: &[CostEdge<C>], from: VertId, to: VertId) -> MinCostFlow<C::Sum> {
+    let plain_edges: Vec<Edge<C>> = edges.iter()
+        .map(|edge| Edge { from: edge.from, to: edge.to, capacity: edge.capacity })
+        .collect();
+    let mut graph = FlowNetwork::new(&plain_edges);
+    graph.add_vert(from.max(to));
+    let mut costs = Vec::with_capacity(edges.len() * 2);
+    for edge in edges {
+        costs.push(edge.cost);
+        costs.push(-edge.cost);
+    }
+
+    let source = from as usize;
+    let sink = to as usize;
+    if source != sink {
+        let mut potentials = bellman_ford(&graph, &costs, source);
+        while let Some(path) = shortest_path(&graph, &costs, &mut potentials, source, sink) {
+            let amount = min_capacity(&graph, &path);
+            for &arc in &path {
+                graph.push(arc, amount);
+            }
+        }
+    }
+
+    let cost = edges.iter().enumerate()
+        .map(|(i, edge)| {
+            let flow = graph.capacities[2 * i + 1];
+            let flow = flow.to_i64().unwrap_or_else(|| panic!("flow {:?} is not a whole i64", flow));
+            flow.checked_mul(edge.cost).unwrap_or_else(|| panic!("cost overflow: {} * {}", flow, edge.cost))
+        })
+        .fold(0i64, |sum, cost| sum.checked_add(cost).unwrap_or_else(|| panic!("cost overflow: {} + {}", sum, cost)));
+
+    MinCostFlow { flows: graph.flows(), cost }
+}
+
+/// Costs of the cheapest paths from the source, used as initial potentials.
+/// Vertices unreachable from the source get 0: they stay unreachable while flow is augmented.
+fn bellman_ford<C: Capacity>(graph: &FlowNetwork<C>, costs: &[i64], source: usize) -> Vec<i64> {
+    let mut dists = vec![NO_DIST; graph.out_arcs.len()];
+    dists[source] = 0;
+    let mut relaxed = true;
+    let mut rounds_count = 0;
+    while relaxed {
+        assert!(rounds_count <= dists.len(), "graph has a cycle of negative cost");
+        relaxed = false;
+        for (arc, &head) in graph.heads.iter().enumerate() {
+            let tail = graph.tail(arc);
+            if dists[tail] != NO_DIST && graph.capacities[arc] > C::zero() && dists[tail] + costs[arc] < dists[head] {
+                dists[head] = dists[tail] + costs[arc];
+                relaxed = true;
+            }
+        }
+        rounds_count += 1;
+    }
+    dists.iter().map(|&dist| if dist == NO_DIST { 0 } else { dist }).collect()
+}
+
+/// Dijkstra over costs reduced by potentials, which keeps them non-negative.
+/// Returns arcs of the cheapest path from the source to the sink and updates potentials.
+fn shortest_path<C: Capacity>(graph: &FlowNetwork<C>, costs: &[i64], potentials: &mut [i64],
+                              source: usize, sink: usize) -> Option<Vec<usize>> {
+    let mut dists = vec![NO_DIST; graph.out_arcs.len()];
+    let mut parent_arcs = vec![NO_ARC; graph.out_arcs.len()];
+    dists[source] = 0;
+    let mut verts = BinaryHeap::new();
+    verts.push(Reverse((0, source)));
+    while let Some(Reverse((dist, vert))) = verts.pop() {
+        if dist > dists[vert] {
+            continue;
+        }
+        for &arc in &graph.out_arcs[vert] {
+            let head = graph.heads[arc];
+            if graph.capacities[arc] > C::zero() {
+                let head_dist = dist + costs[arc] + potentials[vert] - potentials[head];
+                if head_dist < dists[head] {
+                    dists[head] = head_dist;
+                    parent_arcs[head] = arc;
+                    verts.push(Reverse((head_dist, head)));
+                }
+            }
+        }
+    }
+
+    if dists[sink] == NO_DIST {
+        return None;
+    }
+
+    for (potential, &dist) in potentials.iter_mut().zip(&dists) {
+        if dist != NO_DIST {
+            *potential += dist;
+        }
+    }
+
+    let mut path = Vec::new();
+    let mut vert = sink;
+    while vert != source {
+        let arc = parent_arcs[vert];
+        path.push(arc);
+        vert = graph.tail(arc);
+    }
+    path.reverse();
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::thread_rng;
+    use w1_flows_in_networks::dinic;
+    use w1_flows_in_networks::test_utils::check_flows;
+    use w1_flows_in_networks::test_utils::flow_amount;
+    use w1_flows_in_networks::test_utils::gen_edges;
+
+    #[test]
+    fn test1() {
+        let graph: Vec<CostEdge> = vec![
+            CostEdge { from: 1, to: 2, capacity: 4, cost: 2 },
+            CostEdge { from: 1, to: 3, capacity: 2, cost: 2 },
+            CostEdge { from: 2, to: 3, capacity: 2, cost: 1 },
+            CostEdge { from: 2, to: 4, capacity: 3, cost: 3 },
+            CostEdge { from: 3, to: 4, capacity: 5, cost: 1 },
+        ];
+
+        let min_cost_flow = min_cost_max_flow(&graph, 1, 4);
+
+        assert_eq!(flow_amount(&min_cost_flow.flows, 1), 6);
+        assert_eq!(min_cost_flow.cost, 24);
+        assert_eq!(min_cost_flow.flows.get(&2).unwrap().get(&3), Some(&2));
+    }
+
+    #[test]
+    fn test_negative_costs() {
+        let graph: Vec<CostEdge> = vec![
+            CostEdge { from: 1, to: 2, capacity: 1, cost: -5 },
+            CostEdge { from: 2, to: 3, capacity: 1, cost: 1 },
+            CostEdge { from: 1, to: 3, capacity: 1, cost: 0 },
+        ];
+
+        let min_cost_flow = min_cost_max_flow(&graph, 1, 3);
+
+        assert_eq!(flow_amount(&min_cost_flow.flows, 1), 2);
+        assert_eq!(min_cost_flow.cost, -4);
+    }
+
+    #[test]
+    fn test_parallel_edges() {
+        let graph: Vec<CostEdge> = vec![
+            CostEdge { from: 1, to: 2, capacity: 2, cost: 7 },
+            CostEdge { from: 1, to: 2, capacity: 2, cost: 3 },
+            CostEdge { from: 2, to: 3, capacity: 3, cost: 0 },
+        ];
+
+        let min_cost_flow = min_cost_max_flow(&graph, 1, 3);
+
+        assert_eq!(flow_amount(&min_cost_flow.flows, 1), 3);
+        assert_eq!(min_cost_flow.cost, 2 * 3 + 7);
+    }
+
+    #[test]
+    fn test_wide_and_f64_capacities() {
+        let graph = vec![
+            CostEdge { from: 1, to: 2, capacity: 5_000_000_000u64, cost: 2 },
+            CostEdge { from: 1, to: 2, capacity: 1_000_000_000, cost: 1 },
+        ];
+        assert_eq!(min_cost_max_flow(&graph, 1, 2).cost, 11_000_000_000);
+
+        let graph = vec![
+            CostEdge { from: 1, to: 2, capacity: 1.0, cost: 4 },
+            CostEdge { from: 2, to: 3, capacity: 2.5, cost: -1 },
+            CostEdge { from: 1, to: 3, capacity: 3.0, cost: 2 },
+        ];
+        assert_eq!(min_cost_max_flow(&graph, 1, 3).cost, 4 - 1 + 2 * 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a whole i64")]
+    fn test_fractional_flow() {
+        let graph = vec![
+            CostEdge { from: 1, to: 2, capacity: 0.5, cost: 1 },
+        ];
+
+        min_cost_max_flow(&graph, 1, 2);
+    }
+
+    #[test]
+    fn test_rnd() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let edges: Vec<CostEdge<u32>> = gen_edges(8, 20, 5).iter()
+                .map(|edge| CostEdge { from: edge.from, to: edge.to, capacity: edge.capacity, cost: rng.gen_range(0, 10) })
+                .collect();
+            let plain_edges: Vec<Edge<u32>> = edges.iter()
+                .map(|edge| Edge { from: edge.from, to: edge.to, capacity: edge.capacity })
+                .collect();
+
+            let min_cost_flow = min_cost_max_flow(&edges, 1, 2);
+
+            check_flows(&plain_edges, &min_cost_flow.flows, 1, 2);
+            assert_eq!(flow_amount(&min_cost_flow.flows, 1), flow_amount(&dinic::max_flow(&plain_edges, 1, 2), 1));
+            assert_eq!(min_cost_flow.cost, min_cost_by_cycle_canceling(&edges, &plain_edges, 1, 2), "edges {:?}", edges);
+        }
+    }
+
+    /// Starts from any maximum flow and cancels negative cycles in the residual graph while there are any
+    fn min_cost_by_cycle_canceling(edges: &[CostEdge<u32>], plain_edges: &[Edge<u32>], from: VertId, to: VertId) -> i64 {
+        let mut graph = FlowNetwork::new(plain_edges);
+        graph.max_flow(from, to);
+        let costs: Vec<i64> = edges.iter().flat_map(|edge| vec![edge.cost, -edge.cost]).collect();
+        let verts_count = graph.out_arcs.len();
+
+        loop {
+            let mut dists = vec![0; verts_count];
+            let mut parent_arcs = vec![NO_ARC; verts_count];
+            let mut last_relaxed = None;
+            for _ in 0..verts_count {
+                last_relaxed = None;
+                for (arc, &head) in graph.heads.iter().enumerate() {
+                    let tail = graph.tail(arc);
+                    if graph.capacities[arc] > 0 && dists[tail] + costs[arc] < dists[head] {
+                        dists[head] = dists[tail] + costs[arc];
+                        parent_arcs[head] = arc;
+                        last_relaxed = Some(head);
+                    }
+                }
+            }
+
+            match last_relaxed {
+                None => break,
+                Some(mut vert) => {
+                    for _ in 0..verts_count {
+                        vert = graph.tail(parent_arcs[vert]);
+                    }
+                    let mut cycle = vec![parent_arcs[vert]];
+                    while graph.tail(*cycle.last().unwrap()) != vert {
+                        let arc = parent_arcs[graph.tail(*cycle.last().unwrap())];
+                        cycle.push(arc);
+                    }
+                    let amount = cycle.iter().map(|&arc| graph.capacities[arc]).min().unwrap();
+                    for &arc in &cycle {
+                        graph.push(arc, amount);
+                    }
+                }
+            }
+        }
+
+        edges.iter().enumerate()
+            .map(|(i, edge)| graph.capacities[2 * i + 1] as i64 * edge.cost)
+            .sum()
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashSet;
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::VertId;
+use w1_flows_in_networks::add_capacities;
+use w1_flows_in_networks::FlowNetwork;
+
+#[derive(Debug)]
+pub struct MinCut<'a, C: 'a + Capacity> {
+    /// vertices still reachable from the source in the residual graph of a maximum flow
+    pub source_side: HashSet<VertId>,
+    /// saturated edges from the source side to the sink side
+    pub edges: Vec<&'a Edge<C>>,
+    /// equals the maximum flow amount, of the wider type as cut edges may add up to more than `C` holds
+    pub capacity: C::Sum,
+}
+
+pub fn min_cut<'a, C: Capacity>(edges: &'a [Edge<C>], from: VertId, to: VertId) -> MinCut<'a, C> {
+    let mut graph = FlowNetwork::new(edges);
+    graph.max_flow(from, to);
+
+    let reachable = graph.reachable_from(from as usize);
+    let source_side = (0..reachable.len())
+        .filter(|&vert| reachable[vert])
+        .map(|vert| vert as VertId)
+        .collect();
+
+    let cut_edges: Vec<&Edge<C>> = edges.iter()
+        .filter(|edge| edge.capacity > C::zero() && reachable[edge.from as usize] && !reachable[edge.to as usize])
+        .collect();
+    let capacity = cut_edges.iter().fold(C::Sum::zero(), |sum, edge| add_capacities(sum, edge.capacity.to_sum()));
+
+    MinCut { source_side, edges: cut_edges, capacity }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use w1_flows_in_networks::test_utils::evacuation_graph;
+    use w1_flows_in_networks::test_utils::for_rnd_edges;
+
+    #[test]
+    fn test1() {
+        let graph = evacuation_graph();
+
+        let min_cut = min_cut(&graph, 1, 5);
+
+        assert_eq!(min_cut.capacity, 6);
+        assert_eq!(min_cut.source_side, [1, 3, 4].iter().cloned().collect());
+        let cut_edges: Vec<(VertId, VertId)> = min_cut.edges.iter().map(|edge| (edge.from, edge.to)).collect();
+        assert_eq!(cut_edges, [(1, 2), (4, 5), (3, 2)]);
+    }
+
+    #[test]
+    fn test_unreachable_sink() {
+        let graph: Vec<Edge> = vec![
+            Edge { from: 1, to: 2, capacity: 2 },
+            Edge { from: 3, to: 4, capacity: 2 },
+        ];
+
+        let min_cut = min_cut(&graph, 1, 4);
+
+        assert_eq!(min_cut.capacity, 0);
+        assert_eq!(min_cut.source_side, [1, 2].iter().cloned().collect());
+        assert!(min_cut.edges.is_empty());
+    }
+
+    #[test]
+    fn test_capacity_wider_than_edges() {
+        let graph = vec![
+            Edge { from: 1, to: 2, capacity: 60_000u16 },
+            Edge { from: 2, to: 4, capacity: 60_000 },
+            Edge { from: 1, to: 3, capacity: 60_000 },
+            Edge { from: 3, to: 4, capacity: 60_000 },
+        ];
+
+        assert_eq!(min_cut(&graph, 1, 4).capacity, 120_000u32);
+    }
+
+    #[test]
+    fn test_rnd() {
+        for_rnd_edges(|edges, expected| {
+            let min_cut = min_cut(edges, 1, 2);
+
+            assert_eq!(min_cut.capacity, expected, "edges {:?}", edges);
+            assert!(min_cut.source_side.contains(&1));
+            assert!(!min_cut.source_side.contains(&2));
+        });
+    }
+}
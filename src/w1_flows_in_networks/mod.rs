@@ -1,118 +1,168 @@
 pub mod p1_evacuating_people;
+pub mod p1_evacuation_over_time;
 pub mod p2_crews_to_flights;
+pub mod p2_flight_schedule;
+pub mod p2_stable_matching;
 pub mod p3_stock_charts;
+pub mod b_matching;
+pub mod circulation;
+pub mod decomposition;
+pub mod dinic;
+pub mod disjoint_paths;
+pub mod dot;
+pub mod global_min_cut;
+pub mod gomory_hu;
+pub mod hopcroft_karp;
+pub mod hungarian;
+pub mod incremental;
+pub mod min_cost_flow;
+pub mod min_cut;
+pub mod multi_terminal;
+pub mod push_relabel;
+mod capacity;
+mod flow_network;
+mod verification;
 
 extern crate core;
 
 use std::collections::HashMap;
 use std::collections::VecDeque;
-use std::collections::HashSet;
-use std::cmp;
+
+pub use self::capacity::Capacity;
+pub use self::flow_network::FlowNetwork;
+pub use self::verification::FlowReport;
+pub use self::verification::Violation;
+pub use self::verification::verify_flow;
 
 #[derive(Debug)]
-pub struct Edge {
+pub struct Edge<C = u16> {
     /// from vertex
     pub from: VertId,
     /// to vertex
     pub to: VertId,
-    pub capacity: u16,
+    pub capacity: C,
 }
 
-pub type VertId = u16;
+/// Edge which costs `cost` per unit of flow through it
+#[derive(Debug)]
+pub struct CostEdge<C = u16> {
+    /// from vertex
+    pub from: VertId,
+    /// to vertex
+    pub to: VertId,
+    pub capacity: C,
+    pub cost: i64,
+}
 
-pub fn max_flow(edges: &[Edge], from: VertId, to: VertId) -> HashMap<VertId, HashMap<VertId, u16>> {
-    let mut vert_to_resid_edges = HashMap::new();
-    for edge in edges {
-        if edge.from != edge.to {
-            let dest_to_capacity = vert_to_resid_edges.entry(edge.from).or_insert_with(|| HashMap::new());
-            *dest_to_capacity.entry(edge.to).or_insert(0u16) += edge.capacity;
-        }
-    }
+/// Edge which must carry at least `lower` and at most `capacity`
+#[derive(Debug)]
+pub struct BoundedEdge<C = u16> {
+    /// from vertex
+    pub from: VertId,
+    /// to vertex
+    pub to: VertId,
+    pub lower: C,
+    pub capacity: C,
+}
 
-    let mut vert_to_flows: HashMap<VertId, HashMap<VertId, u16>> = HashMap::new();
-    loop {
-        let path = shortest_path(&vert_to_resid_edges, from, to);
+/// Edge which can carry flow either way, both directions share its capacity
+#[derive(Debug)]
+pub struct UndirectedEdge<C = u16> {
+    /// one end
+    pub a: VertId,
+    /// another end
+    pub b: VertId,
+    pub capacity: C,
+}
 
-        if path.is_empty() {
-            break;
-        }
+/// Edge which takes `transit_time` steps to pass and lets in at most `capacity` every step
+#[derive(Debug)]
+pub struct TimedEdge<C = u16> {
+    /// from vertex
+    pub from: VertId,
+    /// to vertex
+    pub to: VertId,
+    pub capacity: C,
+    pub transit_time: u16,
+}
 
-        let min_capacity = min_capacity(&vert_to_resid_edges, &path);
+pub type VertId = u16;
 
-        update_residuals(&mut vert_to_resid_edges, &path, min_capacity);
+/// from vertex -> to vertex -> amount
+pub type Flows<C = u16> = HashMap<VertId, HashMap<VertId, C>>;
 
-        update_flows(&mut vert_to_flows, &path, min_capacity);
+/// Edmonds-Karp: augments flow along shortest paths while there are any.
+/// Parallel edges are merged, so amounts are `C::Sum`.
+pub fn max_flow<C: Capacity>(edges: &[Edge<C>], from: VertId, to: VertId) -> Flows<C::Sum> {
+    let mut network = FlowNetwork::new(edges);
+    network.add_vert(from.max(to));
+    if from != to {
+        while let Some(path) = shortest_path(&network, from as usize, to as usize) {
+            let min_capacity = min_capacity(&network, &path);
+            for &arc in &path {
+                network.push(arc, min_capacity);
+            }
+        }
     }
+    network.flows()
+}
 
-    vert_to_flows
+/// Flow of a maximum flow on every edge by its position in the input.
+/// Unlike `max_flow`, parallel edges and self-loops keep their own amounts.
+pub fn max_edge_flows<C: Capacity>(edges: &[Edge<C>], from: VertId, to: VertId) -> Vec<C> {
+    let mut network = FlowNetwork::new(edges);
+    network.max_flow(from, to);
+    network.edge_flows()
 }
 
-fn shortest_path(vert_to_edges: &HashMap<VertId, HashMap<VertId, u16>>, from: VertId, to: VertId) -> Vec<VertId> {
+/// (from vertex, to vertex, amount) of net flow of a maximum flow on every edge by its position in the input
+pub fn undirected_max_flow<C: Capacity>(edges: &[UndirectedEdge<C>], from: VertId, to: VertId) -> Vec<(VertId, VertId, C)> {
+    let mut network = FlowNetwork::new(&[]);
+    for edge in edges {
+        network.add_undirected_edge(edge.a, edge.b, edge.capacity);
+    }
+    network.max_flow(from, to);
+    (0..edges.len()).map(|edge| network.directed_flow(edge)).collect()
+}
+
+/// Arcs of a path with the least number of non-saturated arcs
+fn shortest_path<C: Capacity>(network: &FlowNetwork<C>, from: usize, to: usize) -> Option<Vec<usize>> {
+    let mut parent_arcs = vec![None; network.out_arcs.len()];
     let mut tasks = VecDeque::new();
-    tasks.push_back(
-        BFSTask { vert: from, path_to_vert: Vec::new() }
-    );
-    let mut visited = HashSet::new();
-    visited.insert(from);
-    loop {
-        let task = tasks.pop_front().unwrap();
-        if let Some(dest_to_capacity) = vert_to_edges.get(&task.vert) {
-            for &dest in dest_to_capacity.keys() {
+    tasks.push_back(from);
+    while let Some(vert) = tasks.pop_front() {
+        for &arc in &network.out_arcs[vert] {
+            let dest = network.heads[arc];
+            if dest != from && parent_arcs[dest].is_none() && network.capacities[arc] > C::zero() {
+                parent_arcs[dest] = Some(arc);
                 if dest == to {
-                    let mut path_to_dest = task.path_to_vert.clone();
-                    path_to_dest.push(task.vert);
-                    path_to_dest.push(dest);
-                    return path_to_dest;
-                }
-                if !visited.contains(&dest) {
-                    visited.insert(dest);
-                    // TODO: can (and should?) we reuse prefixes of path?
-                    let mut path_to_dest = task.path_to_vert.clone();
-                    path_to_dest.push(task.vert);
-                    tasks.push_back(
-                        BFSTask { vert: dest, path_to_vert: path_to_dest }
-                    );
+                    let mut path = Vec::new();
+                    let mut path_vert = to;
+                    while let Some(arc) = parent_arcs[path_vert] {
+                        path.push(arc);
+                        path_vert = network.tail(arc);
+                    }
+                    path.reverse();
+                    return Some(path);
                 }
+                tasks.push_back(dest);
             }
         }
-
-        if tasks.is_empty() {
-            break;
-        }
-    }
-    Vec::new()
-}
-
-struct BFSTask {
-    vert: VertId,
-    path_to_vert: Vec<VertId>,
-}
-
-fn min_capacity(vert_to_edges: &HashMap<VertId, HashMap<VertId, u16>>, path: &[VertId]) -> u16 {
-    let mut min_cap = *vert_to_edges.get(&path[0]).unwrap().get(&path[1]).unwrap();
-    for i in 2..path.len() {
-        min_cap = cmp::min(min_cap, *vert_to_edges.get(&path[i - 1]).unwrap().get(&path[i]).unwrap());
     }
-    min_cap
+    None
 }
 
-fn update_residuals(vert_to_resid_edges: &mut HashMap<VertId, HashMap<VertId, u16>>, path: &[VertId], amount: u16) {
-    for i in 1..path.len() {
-        let from = path[i - 1];
-        let to = path[i];
-        {
-            let mut dests = vert_to_resid_edges.get_mut(&from).unwrap();
-            let capacity = dests.remove(&to).unwrap();
-            if capacity > amount {
-                dests.insert(to, capacity - amount);
-            }
+fn min_capacity<C: Capacity>(network: &FlowNetwork<C>, path: &[usize]) -> C {
+    let mut min_cap = network.capacities[path[0]];
+    for &arc in &path[1..] {
+        if network.capacities[arc] < min_cap {
+            min_cap = network.capacities[arc];
         }
-        let mut dests = vert_to_resid_edges.entry(to).or_insert_with(|| HashMap::new());
-        *dests.entry(from).or_insert(0) += amount;
     }
+    min_cap
 }
 
-fn update_flows(vert_to_flows: &mut HashMap<VertId, HashMap<VertId, u16>>, path: &[VertId], amount: u16) {
+fn update_flows<C: Capacity>(vert_to_flows: &mut Flows<C>, path: &[VertId], amount: C) {
     for i in 1..path.len() {
         let mut from_to_amount = amount;
         let from = path[i - 1];
@@ -124,13 +174,13 @@ fn update_flows(vert_to_flows: &mut HashMap<VertId, HashMap<VertId, u16>>, path:
                 {
                     if let Some(to_from_amount) = to_flows.get_mut(&from) {
                         if *to_from_amount == amount {
-                            from_to_amount = 0;
+                            from_to_amount = C::zero();
                             remove_to_from_flow = true;
                         } else if *to_from_amount > amount {
-                            *to_from_amount -= amount;
-                            from_to_amount = 0;
+                            *to_from_amount = sub_capacities(*to_from_amount, amount);
+                            from_to_amount = C::zero();
                         } else {
-                            from_to_amount -= *to_from_amount;
+                            from_to_amount = sub_capacities(from_to_amount, *to_from_amount);
                             remove_to_from_flow = true;
                         }
                     }
@@ -146,9 +196,217 @@ fn update_flows(vert_to_flows: &mut HashMap<VertId, HashMap<VertId, u16>>, path:
         if remove_to_flows {
             vert_to_flows.remove(&to);
         }
-        if from_to_amount > 0 {
-            let flows = vert_to_flows.entry(from).or_insert_with(|| HashMap::new());
-            *flows.entry(to).or_insert(0) += amount;
+        if from_to_amount > C::zero() {
+            let flows = vert_to_flows.entry(from).or_default();
+            let flow = flows.entry(to).or_insert_with(C::zero);
+            *flow = add_capacities(*flow, from_to_amount);
+        }
+    }
+}
+
+/// Ids for a super source and a super sink that follow the max used vertex id
+fn super_terminals(max_vert: VertId) -> (VertId, VertId) {
+    let super_source = max_vert.checked_add(1).expect("no vertex id left for super source");
+    let super_sink = max_vert.checked_add(2).expect("no vertex id left for super sink");
+    (super_source, super_sink)
+}
+
+fn add_capacities<C: Capacity>(a: C, b: C) -> C {
+    a.checked_add(b).unwrap_or_else(|| panic!("capacity overflow: {:?} + {:?}", a, b))
+}
+
+fn sub_capacities<C: Capacity>(a: C, b: C) -> C {
+    a.checked_sub(b).unwrap_or_else(|| panic!("capacity overflow: {:?} - {:?}", a, b))
+}
+
+#[cfg(test)]
+pub mod test_utils {
+    use super::*;
+    use rand::Rng;
+    use rand::thread_rng;
+
+    /// Random edges between vertices 1..=max_vertices, may contain parallel edges and self-loops
+    pub fn gen_edges(max_vertices: VertId, max_edges: usize, max_capacity: u32) -> Vec<Edge<u32>> {
+        let mut rng = thread_rng();
+        let vertices_count = rng.gen_range(2, max_vertices + 1);
+        let edges_count = rng.gen_range(0, max_edges + 1);
+        (0..edges_count)
+            .map(|_| Edge {
+                from: rng.gen_range(1, vertices_count + 1),
+                to: rng.gen_range(1, vertices_count + 1),
+                capacity: rng.gen_range(0, max_capacity + 1),
+            })
+            .collect()
+    }
+
+    /// Roads of the evacuation sample: maximum flow from 1 to 5 is 6, the minimum cut leaves 1, 3 and 4 on its source side
+    pub fn evacuation_graph() -> Vec<Edge> {
+        vec![
+            Edge { from: 1, to: 2, capacity: 2 },
+            Edge { from: 2, to: 5, capacity: 5 },
+            Edge { from: 1, to: 3, capacity: 6 },
+            Edge { from: 3, to: 4, capacity: 2 },
+            Edge { from: 4, to: 5, capacity: 1 },
+            Edge { from: 3, to: 2, capacity: 3 },
+            Edge { from: 2, to: 4, capacity: 1 },
+        ]
+    }
+
+    /// Calls the check with random edges and the amount of their maximum flow from 1 to 2 found by Edmonds-Karp
+    pub fn for_rnd_edges<F: FnMut(&[Edge<u32>], u64)>(mut check: F) {
+        for _ in 0..1000 {
+            let edges = gen_edges(10, 30, 10);
+            let expected = flow_amount(&max_flow(&edges, 1, 2), 1);
+            check(&edges, expected);
+        }
+    }
+
+    /// Asserts that flows from 1 to 2 found for random edges are valid and as large as those of Edmonds-Karp
+    pub fn check_max_flow_rnd<F: Fn(&[Edge<u32>], VertId, VertId) -> Flows<u64>>(max_flow: F) {
+        for_rnd_edges(|edges, expected| {
+            let flows = max_flow(edges, 1, 2);
+            check_flows(edges, &flows, 1, 2);
+            assert_eq!(flow_amount(&flows, 1), expected, "edges {:?}", edges);
+        });
+    }
+
+    pub fn flow_amount<C: Capacity>(vert_to_flows: &Flows<C>, from: VertId) -> C {
+        match vert_to_flows.get(&from) {
+            Some(flows) => flows.values().fold(C::zero(), |sum, a| add_capacities(sum, *a)),
+            None => C::zero()
+        }
+    }
+
+    /// Asserts that flows fit into capacities and are conserved in every vertex except from and to
+    pub fn check_flows<C: Capacity>(edges: &[Edge<C>], vert_to_flows: &Flows<C::Sum>, from: VertId, to: VertId) {
+        let mut capacities: HashMap<(VertId, VertId), C::Sum> = HashMap::new();
+        for edge in edges {
+            let capacity = capacities.entry((edge.from, edge.to)).or_insert_with(C::Sum::zero);
+            *capacity = add_capacities(*capacity, edge.capacity.to_sum());
+        }
+        let mut vert_to_balance: HashMap<VertId, i64> = HashMap::new();
+        for (&flow_from, flows) in vert_to_flows {
+            for (&flow_to, &flow) in flows {
+                assert!(flow > C::Sum::zero(), "zero flow from {} to {}", flow_from, flow_to);
+                let capacity = capacities.get(&(flow_from, flow_to)).cloned().unwrap_or_else(C::Sum::zero);
+                assert!(flow <= capacity, "flow {:?} from {} to {} exceeds capacity {:?}", flow, flow_from, flow_to, capacity);
+                *vert_to_balance.entry(flow_from).or_insert(0) -= flow.to_i64().unwrap();
+                *vert_to_balance.entry(flow_to).or_insert(0) += flow.to_i64().unwrap();
+            }
+        }
+        for (&vert, &balance) in &vert_to_balance {
+            if vert != from && vert != to {
+                assert_eq!(balance, 0, "flow is not conserved in vertex {}, edges {:?}", vert, edges);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use w1_flows_in_networks::test_utils::check_flows;
+    use w1_flows_in_networks::test_utils::flow_amount;
+    use w1_flows_in_networks::test_utils::for_rnd_edges;
+    use w1_flows_in_networks::test_utils::gen_edges;
+
+    #[test]
+    fn test_parallel_edges() {
+        let edges = vec![
+            Edge { from: 1, to: 2, capacity: 3 },
+            Edge { from: 1, to: 2, capacity: 4 },
+            Edge { from: 2, to: 2, capacity: 5 },
+            Edge { from: 2, to: 3, capacity: 6 },
+        ];
+
+        let flows = max_edge_flows(&edges, 1, 3);
+
+        assert_eq!(flows.len(), 4);
+        assert_eq!(flows[0] + flows[1], 6);
+        assert!(flows[0] <= 3 && flows[1] <= 4);
+        assert_eq!(flows[2], 0);
+        assert_eq!(flows[3], 6);
+    }
+
+    #[test]
+    fn test_parallel_edges_wider_than_capacity() {
+        let edges = vec![
+            Edge { from: 1, to: 2, capacity: 60_000u16 },
+            Edge { from: 1, to: 2, capacity: 60_000 },
+        ];
+
+        let flows = max_flow(&edges, 1, 2);
+
+        assert_eq!(flows[&1][&2], 120_000u32);
+    }
+
+    #[test]
+    fn test_edge_flows_rnd() {
+        for_rnd_edges(|edges, expected| {
+            let flows = max_edge_flows(edges, 1, 2);
+
+            assert_eq!(flows.len(), edges.len());
+            let mut vert_to_flows = HashMap::new();
+            for (edge, &flow) in edges.iter().zip(&flows) {
+                assert!(flow <= edge.capacity, "flow {} on edge {:?}", flow, edge);
+                if edge.from != edge.to && flow > 0 {
+                    update_flows(&mut vert_to_flows, &[edge.from, edge.to], flow.to_sum());
+                }
+            }
+            check_flows(edges, &vert_to_flows, 1, 2);
+            assert_eq!(flow_amount(&vert_to_flows, 1), expected);
+        });
+    }
+
+    #[test]
+    fn test_undirected_edges() {
+        let edges = vec![
+            UndirectedEdge { a: 1, b: 2, capacity: 3 },
+            UndirectedEdge { a: 3, b: 2, capacity: 2 },
+            UndirectedEdge { a: 3, b: 4, capacity: 5 },
+            UndirectedEdge { a: 1, b: 3, capacity: 3 },
+            UndirectedEdge { a: 4, b: 2, capacity: 1 },
+        ];
+
+        let flows = undirected_max_flow(&edges, 1, 4);
+
+        assert_eq!(flows[0], (1, 2, 3));
+        assert_eq!(flows[1], (2, 3, 2));
+        assert_eq!(flows[2], (3, 4, 5));
+        assert_eq!(flows[3], (1, 3, 3));
+        assert_eq!(flows[4], (2, 4, 1));
+    }
+
+    #[test]
+    fn test_undirected_rnd() {
+        for _ in 0..1000 {
+            let directed = gen_edges(10, 30, 10);
+            let edges: Vec<UndirectedEdge<u32>> = directed.iter()
+                .map(|edge| UndirectedEdge { a: edge.from, b: edge.to, capacity: edge.capacity })
+                .collect();
+
+            let flows = undirected_max_flow(&edges, 1, 2);
+
+            // two opposite edges for every undirected one carry the same maximum flow
+            let mut both_ways = FlowNetwork::new(&[]);
+            let mut vert_to_flows = HashMap::new();
+            for (edge, &(from, to, flow)) in edges.iter().zip(&flows) {
+                both_ways.add_edge(edge.a, edge.b, edge.capacity);
+                both_ways.add_edge(edge.b, edge.a, edge.capacity);
+                assert!((from, to) == (edge.a, edge.b) || (from, to) == (edge.b, edge.a));
+                assert!(flow <= edge.capacity);
+                if from != to && flow > 0 {
+                    update_flows(&mut vert_to_flows, &[from, to], flow.to_sum());
+                }
+            }
+            let both_ways_edges: Vec<Edge<u32>> = edges.iter()
+                .flat_map(|edge| vec![
+                    Edge { from: edge.a, to: edge.b, capacity: edge.capacity },
+                    Edge { from: edge.b, to: edge.a, capacity: edge.capacity },
+                ])
+                .collect();
+            check_flows(&both_ways_edges, &vert_to_flows, 1, 2);
+            assert_eq!(flow_amount(&vert_to_flows, 1), both_ways.max_flow(1, 2), "edges {:?}", edges);
         }
     }
 }
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::Flows;
+use w1_flows_in_networks::VertId;
+use w1_flows_in_networks::FlowNetwork;
+use w1_flows_in_networks::super_terminals;
+
+/// terminal vertex -> max amount it can send or receive, `None` for unlimited
+pub type Terminals<C = u16> = HashMap<VertId, Option<C>>;
+
+/// Maximum flow from any of the sources to any of the sinks.
+/// Terminals are connected to a super source and a super sink, which do not show up in the result.
+pub fn max_flow<C: Capacity>(edges: &[Edge<C>], sources: &Terminals<C>, sinks: &Terminals<C>) -> Flows<C::Sum> {
+    let max_vert = edges.iter()
+        .map(|edge| edge.from.max(edge.to))
+        .chain(sources.keys().cloned())
+        .chain(sinks.keys().cloned())
+        .max()
+        .unwrap_or(0);
+    let (super_source, super_sink) = super_terminals(max_vert);
+
+    let mut graph = FlowNetwork::new(edges);
+    // a terminal without a limit can not pass more than its edges take,
+    // it gets an edge of the same capacity for each of them, so their total never has to fit into `C`
+    for (&source, &capacity) in sources {
+        assert!(!sinks.contains_key(&source), "vertex {} is both a source and a sink", source);
+        match capacity {
+            Some(capacity) => {
+                graph.add_edge(super_source, source, capacity);
+            }
+            None => {
+                for edge in edges.iter().filter(|edge| edge.from == source && edge.to != source) {
+                    graph.add_edge(super_source, source, edge.capacity);
+                }
+            }
+        }
+    }
+    for (&sink, &capacity) in sinks {
+        match capacity {
+            Some(capacity) => {
+                graph.add_edge(sink, super_sink, capacity);
+            }
+            None => {
+                for edge in edges.iter().filter(|edge| edge.to == sink && edge.from != sink) {
+                    graph.add_edge(sink, super_sink, edge.capacity);
+                }
+            }
+        }
+    }
+
+    graph.max_flow(super_source, super_sink);
+
+    let mut vert_to_flows = graph.flows();
+    vert_to_flows.remove(&super_source);
+    for sink in sinks.keys() {
+        let mut remove_sink_flows = false;
+        if let Some(sink_flows) = vert_to_flows.get_mut(sink) {
+            sink_flows.remove(&super_sink);
+            remove_sink_flows = sink_flows.is_empty();
+        }
+        if remove_sink_flows {
+            vert_to_flows.remove(sink);
+        }
+    }
+    vert_to_flows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::thread_rng;
+    use w1_flows_in_networks::dinic;
+    use w1_flows_in_networks::test_utils::check_flows;
+    use w1_flows_in_networks::test_utils::flow_amount;
+    use w1_flows_in_networks::test_utils::gen_edges;
+
+    #[test]
+    fn test1() {
+        let graph = vec![
+            Edge { from: 1, to: 3, capacity: 4 },
+            Edge { from: 2, to: 3, capacity: 4 },
+            Edge { from: 3, to: 4, capacity: 5 },
+            Edge { from: 3, to: 5, capacity: 2 },
+            Edge { from: 4, to: 5, capacity: 1 },
+        ];
+        let sources = [(1, None), (2, None)].iter().cloned().collect();
+        let sinks = [(4, None), (5, None)].iter().cloned().collect();
+
+        let flows = max_flow(&graph, &sources, &sinks);
+
+        assert_eq!(net_inflow(&flows, &sinks), 7);
+        assert_eq!(flows.get(&3).unwrap().get(&5), Some(&2));
+        assert_eq!(flows.get(&4), None);
+    }
+
+    #[test]
+    fn test_terminal_capacities() {
+        let graph = vec![
+            Edge { from: 1, to: 3, capacity: 4 },
+            Edge { from: 2, to: 3, capacity: 4 },
+            Edge { from: 3, to: 4, capacity: 5 },
+            Edge { from: 3, to: 5, capacity: 2 },
+        ];
+        let sources = [(1, Some(1)), (2, None)].iter().cloned().collect();
+        let sinks = [(4, Some(3)), (5, None)].iter().cloned().collect();
+
+        let flows = max_flow(&graph, &sources, &sinks);
+
+        assert_eq!(net_inflow(&flows, &sinks), 5);
+        assert_eq!(flows.get(&1).unwrap().get(&3), Some(&1));
+        assert_eq!(flows.get(&2).unwrap().get(&3), Some(&4));
+    }
+
+    #[test]
+    fn test_unlimited_terminals_wider_than_capacities() {
+        let graph = vec![
+            Edge { from: 1, to: 2, capacity: 60_000u16 },
+            Edge { from: 1, to: 2, capacity: 60_000 },
+        ];
+        let sources = [(1, None)].iter().cloned().collect();
+        let sinks = [(2, None)].iter().cloned().collect();
+
+        let flows = max_flow(&graph, &sources, &sinks);
+
+        assert_eq!(flows[&1][&2], 120_000);
+    }
+
+    #[test]
+    fn test_rnd() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let edges = gen_edges(10, 30, 10);
+            let mut sources: Terminals<u32> = HashMap::new();
+            let mut sinks: Terminals<u32> = HashMap::new();
+            for vert in 1..11 {
+                let capacity = if rng.gen() { Some(rng.gen_range(0, 10)) } else { None };
+                match rng.gen_range(0, 3) {
+                    0 => { sources.insert(vert, capacity); }
+                    1 => { sinks.insert(vert, capacity); }
+                    _ => {}
+                }
+            }
+
+            let flows = max_flow(&edges, &sources, &sinks);
+
+            let mut edges_by_hand: Vec<Edge<u32>> = edges.iter()
+                .map(|edge| Edge { from: edge.from, to: edge.to, capacity: edge.capacity })
+                .collect();
+            for (&source, &capacity) in &sources {
+                edges_by_hand.push(Edge { from: 100, to: source, capacity: capacity.unwrap_or(1000) });
+            }
+            for (&sink, &capacity) in &sinks {
+                edges_by_hand.push(Edge { from: sink, to: 101, capacity: capacity.unwrap_or(1000) });
+            }
+            let expected = flow_amount(&dinic::max_flow(&edges_by_hand, 100, 101), 100);
+
+            assert_eq!(net_inflow(&flows, &sinks), expected as i64, "edges {:?}, sources {:?}, sinks {:?}", edges, sources, sinks);
+            let mut flows_with_terminals = flows.clone();
+            for (&source, &capacity) in &sources {
+                let sent = net_inflow(&flows, &[(source, capacity)].iter().cloned().collect());
+                assert!(capacity.is_none_or(|capacity| -sent <= capacity as i64));
+                if sent < 0 {
+                    flows_with_terminals.entry(100).or_default().insert(source, -sent as u64);
+                }
+            }
+            for (&sink, &capacity) in &sinks {
+                let received = net_inflow(&flows, &[(sink, capacity)].iter().cloned().collect());
+                assert!(capacity.is_none_or(|capacity| received <= capacity as i64));
+                if received > 0 {
+                    flows_with_terminals.entry(sink).or_default().insert(101, received as u64);
+                }
+            }
+            check_flows(&edges_by_hand, &flows_with_terminals, 100, 101);
+        }
+    }
+
+    fn net_inflow(vert_to_flows: &Flows<u64>, verts: &Terminals<u32>) -> i64 {
+        let mut inflow = 0;
+        for (from, flows) in vert_to_flows {
+            for (to, &flow) in flows {
+                if verts.contains_key(to) {
+                    inflow += flow as i64;
+                }
+                if verts.contains_key(from) {
+                    inflow -= flow as i64;
+                }
+            }
+        }
+        inflow
+    }
+}
@@ -1,34 +1,85 @@
+use std::collections::HashMap;
 use w1_flows_in_networks::VertId;
-use w1_flows_in_networks::max_flow;
 use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::FlowNetwork;
+use w1_flows_in_networks::Flows;
+use w1_flows_in_networks::update_flows;
 
-pub fn max_flow_amount(edges: &[Edge], from: VertId, to: VertId) -> u32 {
-    let vert_to_flows = max_flow(edges, from, to);
-    match vert_to_flows.get(&from) {
-        Some(flows) => flows.values().map(|a| *a as u32).sum(),
-        None => 0
+/// Amount is of the wider `C::Sum` type, so it does not overflow when edges of `C` add up to more than `C` holds
+pub fn max_flow_amount<C: Capacity>(edges: &[Edge<C>], from: VertId, to: VertId) -> C::Sum {
+    FlowNetwork::new(edges).max_flow(from, to)
+}
+
+/// Same as `max_flow_amount`, but at most `vert_capacities[v]` people can pass through a vertex v.
+/// Vertices without a capacity are not limited.
+pub fn max_flow_amount_with_vert_capacities<C: Capacity>(edges: &[Edge<C>], vert_capacities: &HashMap<VertId, C>,
+                                                         from: VertId, to: VertId) -> C::Sum {
+    split_verts(edges, vert_capacities, from, to).1
+}
+
+/// Flows along the original edges of a maximum flow limited by vertex capacities, parallel edges are merged
+pub fn max_flow_with_vert_capacities<C: Capacity>(edges: &[Edge<C>], vert_capacities: &HashMap<VertId, C>,
+                                                  from: VertId, to: VertId) -> Flows<C::Sum> {
+    let (network, _) = split_verts(edges, vert_capacities, from, to);
+    let mut vert_to_flows = HashMap::new();
+    for (i, edge) in edges.iter().enumerate() {
+        let flow = network.flow(i);
+        if flow > C::zero() {
+            update_flows(&mut vert_to_flows, &[edge.from, edge.to], flow.to_sum());
+        }
     }
+    vert_to_flows
+}
+
+/// Every capped vertex v is split into v, which gets entering edges, and a new vertex, which gets leaving edges,
+/// connected by an edge of v's capacity. Edges of the returned network start with the original ones.
+fn split_verts<C: Capacity>(edges: &[Edge<C>], vert_capacities: &HashMap<VertId, C>,
+                            from: VertId, to: VertId) -> (FlowNetwork<C>, C::Sum) {
+    let max_vert = edges.iter()
+        .map(|edge| edge.from.max(edge.to))
+        .chain(vert_capacities.keys().cloned())
+        .fold(from.max(to), VertId::max);
+    let mut vert_to_out: HashMap<VertId, VertId> = HashMap::new();
+    let mut next_vert = max_vert;
+    for &vert in vert_capacities.keys() {
+        next_vert = next_vert.checked_add(1).expect("no vertex id left to split vertices");
+        vert_to_out.insert(vert, next_vert);
+    }
+
+    let split_edges: Vec<Edge<C>> = edges.iter()
+        .map(|edge| Edge {
+            from: vert_to_out.get(&edge.from).cloned().unwrap_or(edge.from),
+            to: edge.to,
+            capacity: edge.capacity,
+        })
+        .collect();
+    let mut network = FlowNetwork::new(&split_edges);
+    for (vert, &capacity) in vert_capacities {
+        network.add_edge(*vert, vert_to_out[vert], capacity);
+    }
+
+    // people must pass the sink to get there
+    let sink = vert_to_out.get(&to).cloned().unwrap_or(to);
+    let amount = network.max_flow(from, sink);
+    (network, amount)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::Rng;
+    use rand::thread_rng;
+    use w1_flows_in_networks::max_flow;
+    use w1_flows_in_networks::test_utils::check_flows;
+    use w1_flows_in_networks::test_utils::evacuation_graph;
+    use w1_flows_in_networks::test_utils::flow_amount;
+    use w1_flows_in_networks::test_utils::gen_edges;
+    use w1_flows_in_networks::verify_flow;
 
     #[test]
     fn test1() {
-        let graph = vec![
-            Edge { from: 1, to: 2, capacity: 2 },
-            Edge { from: 2, to: 5, capacity: 5 },
-            Edge { from: 1, to: 3, capacity: 6 },
-            Edge { from: 3, to: 4, capacity: 2 },
-            Edge { from: 4, to: 5, capacity: 1 },
-            Edge { from: 3, to: 2, capacity: 3 },
-            Edge { from: 2, to: 4, capacity: 1 },
-        ];
-
-        let max_flow_amount = max_flow_amount(&graph, 1, 5);
-
-        assert_eq!(max_flow_amount, 6);
+        check_max_flow_amount(&evacuation_graph(), 1, 5);
     }
 
     #[test]
@@ -41,8 +92,114 @@ mod tests {
             Edge { from: 2, to: 4, capacity: 10_000 },
         ];
 
-        let max_flow_amount = max_flow_amount(&graph, 1, 4);
+        check_max_flow_amount(&graph, 1, 4);
+    }
+
+    #[test]
+    fn test_large_capacities() {
+        let graph = vec![
+            Edge { from: 1, to: 2, capacity: 3_000_000_000u64 },
+            Edge { from: 1, to: 2, capacity: 3_000_000_000 },
+            Edge { from: 2, to: 3, capacity: 10_000_000_000 },
+            Edge { from: 3, to: 2, capacity: 5_000_000_000 },
+        ];
+
+        check_max_flow_amount(&graph, 1, 3);
+    }
+
+    #[test]
+    fn test_f64_capacities() {
+        let graph = vec![
+            Edge { from: 1, to: 2, capacity: 0.5 },
+            Edge { from: 1, to: 3, capacity: 1.25 },
+            Edge { from: 3, to: 2, capacity: 1.0 },
+        ];
+
+        check_max_flow_amount(&graph, 1, 2);
+    }
+
+    #[test]
+    fn test_amount_wider_than_capacities() {
+        let graph = vec![
+            Edge { from: 1, to: 2, capacity: 60_000u16 },
+            Edge { from: 2, to: 4, capacity: 60_000 },
+            Edge { from: 1, to: 3, capacity: 60_000 },
+            Edge { from: 3, to: 4, capacity: 60_000 },
+        ];
+
+        assert_eq!(max_flow_amount(&graph, 1, 4), 120_000u32);
+    }
+
+    /// Compares with the amount of a flow proven maximum by an equal cut
+    fn check_max_flow_amount<C: Capacity>(graph: &[Edge<C>], from: VertId, to: VertId) {
+        let report = verify_flow(graph, from, to, &max_flow(graph, from, to));
+        assert!(report.is_max_flow(), "{:?}", report);
+        assert_eq!(max_flow_amount(graph, from, to), report.amount);
+    }
+
+    #[test]
+    fn test_vert_capacities() {
+        let graph = evacuation_graph();
+        let vert_capacities = [(2, 4), (3, 1)].iter().cloned().collect();
+
+        let flows = max_flow_with_vert_capacities(&graph, &vert_capacities, 1, 5);
+
+        assert_eq!(max_flow_amount_with_vert_capacities(&graph, &vert_capacities, 1, 5), 3);
+        assert_eq!(flow_amount(&flows, 1), 3);
+        check_flows(&graph, &flows, 1, 5);
+        let through_2 = flows.get(&2).map_or(0, |flows| flows.values().sum());
+        assert!(through_2 <= 4);
+    }
+
+    #[test]
+    fn test_terminal_capacities() {
+        let graph: Vec<Edge> = vec![
+            Edge { from: 1, to: 2, capacity: 5 },
+            Edge { from: 1, to: 3, capacity: 5 },
+            Edge { from: 2, to: 4, capacity: 5 },
+            Edge { from: 3, to: 4, capacity: 5 },
+        ];
+
+        let source_capped = [(1, 7)].iter().cloned().collect();
+        assert_eq!(max_flow_amount_with_vert_capacities(&graph, &source_capped, 1, 4), 7);
+        let sink_capped = [(4, 6)].iter().cloned().collect();
+        assert_eq!(max_flow_amount_with_vert_capacities(&graph, &sink_capped, 1, 4), 6);
+    }
+
+    #[test]
+    fn test_vert_capacities_rnd() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let edges = gen_edges(10, 30, 10);
+            let mut vert_capacities = HashMap::new();
+            for vert in 1..11 {
+                if rng.gen() {
+                    vert_capacities.insert(vert, rng.gen_range(0, 15));
+                }
+            }
+
+            let flows = max_flow_with_vert_capacities(&edges, &vert_capacities, 1, 2);
+
+            check_flows(&edges, &flows, 1, 2);
+            let amount = max_flow_amount_with_vert_capacities(&edges, &vert_capacities, 1, 2);
+            assert_eq!(flow_amount(&flows, 1), amount);
+
+            // out-copy of a capped vertex v is v + 100, maximum found by Edmonds-Karp instead of Dinic
+            let out = |vert: VertId| if vert_capacities.contains_key(&vert) { vert + 100 } else { vert };
+            let mut split_edges: Vec<Edge<u32>> = edges.iter()
+                .map(|edge| Edge { from: out(edge.from), to: edge.to, capacity: edge.capacity })
+                .collect();
+            for (&vert, &capacity) in &vert_capacities {
+                split_edges.push(Edge { from: vert, to: vert + 100, capacity });
+            }
+            let expected = flow_amount(&max_flow(&split_edges, 1, out(2)), 1);
+            assert_eq!(amount, expected, "edges {:?}, capacities {:?}", edges, vert_capacities);
 
-        assert_eq!(max_flow_amount, 20_000);
+            for (vert, &capacity) in &vert_capacities {
+                let outflow: u64 = flows.get(vert).map_or(0, |flows| flows.values().sum());
+                let inflow: u64 = flows.values().filter_map(|flows| flows.get(vert)).sum();
+                assert!(outflow.max(inflow) <= u64::from(capacity), "vertex {}, edges {:?}, capacities {:?}", vert, edges, vert_capacities);
+            }
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,174 @@
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::FlowNetwork;
+use w1_flows_in_networks::TimedEdge;
+use w1_flows_in_networks::VertId;
+
+/// How many of `people` waiting at `from` at time 0 can get to `to` by time `deadline`.
+/// Works on the time-expanded network: a copy of every vertex for every time step,
+/// edges from each copy lead to copies `transit_time` steps later and people may wait in a vertex.
+pub fn max_evacuated<C: Capacity>(edges: &[TimedEdge<C>], from: VertId, to: VertId, people: C, deadline: u16)
+                                  -> C::Sum {
+    if from == to {
+        return people.to_sum();
+    }
+
+    let verts = timed_verts(edges, from, to);
+    let verts_count = verts.len();
+    let steps_count = deadline as usize + 1;
+    // copies outnumber vertex ids soon, so the network is built over indices
+    let expanded_vert = |vert: VertId, time: usize| time * verts_count + verts.binary_search(&vert).unwrap();
+
+    let mut network = FlowNetwork::new(&[]);
+    let source = expanded_vert(from, steps_count);
+    network.add_edge_by_index(source, expanded_vert(from, 0), people);
+    for time in 0..steps_count {
+        for edge in edges {
+            let arrival = time + edge.transit_time as usize;
+            if arrival < steps_count {
+                let (departure, destination) = (expanded_vert(edge.from, time), expanded_vert(edge.to, arrival));
+                network.add_edge_by_index(departure, destination, edge.capacity);
+            }
+        }
+        if time + 1 < steps_count {
+            // waiting in a vertex, never more than `people` can be there
+            for &vert in &verts {
+                network.add_edge_by_index(expanded_vert(vert, time), expanded_vert(vert, time + 1), people);
+            }
+        }
+    }
+
+    network.max_flow_by_index(source, expanded_vert(to, deadline as usize))
+}
+
+/// The earliest time by which all `people` waiting at `from` at time 0 can get to `to`,
+/// `None` if `to` can not be reached at all or not everyone gets there by the latest deadline `u16` holds
+pub fn quickest_evacuation<C: Capacity>(edges: &[TimedEdge<C>], from: VertId, to: VertId, people: C) -> Option<u16> {
+    if from == to {
+        return Some(0);
+    }
+    let everyone_by = |deadline: u16| max_evacuated(edges, from, to, people, deadline) >= people.to_sum();
+    if everyone_by(0) {
+        return Some(0);
+    }
+    let plain_edges: Vec<Edge<C>> = edges.iter()
+        .map(|edge| Edge { from: edge.from, to: edge.to, capacity: edge.capacity })
+        .collect();
+    if FlowNetwork::new(&plain_edges).max_flow(from, to) == C::Sum::zero() {
+        return None;
+    }
+
+    // everyone_by(late) is true, everyone_by(early) is false
+    let mut early = 0u16;
+    let mut late = loop {
+        if early == u16::MAX {
+            return None;
+        }
+        let next = early.saturating_mul(2).max(1);
+        if everyone_by(next) {
+            break next;
+        }
+        early = next;
+    };
+    while late - early > 1 {
+        let middle = early + (late - early) / 2;
+        if everyone_by(middle) {
+            late = middle;
+        } else {
+            early = middle;
+        }
+    }
+    Some(late)
+}
+
+/// Sorted vertices of the edges and the terminals
+fn timed_verts<C: Capacity>(edges: &[TimedEdge<C>], from: VertId, to: VertId) -> Vec<VertId> {
+    let mut verts: Vec<VertId> = edges.iter().flat_map(|edge| vec![edge.from, edge.to]).collect();
+    verts.push(from);
+    verts.push(to);
+    verts.sort();
+    verts.dedup();
+    verts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::thread_rng;
+    use w1_flows_in_networks::test_utils::gen_edges;
+
+    #[test]
+    fn test1() {
+        let graph: Vec<TimedEdge> = vec![
+            TimedEdge { from: 1, to: 2, capacity: 2, transit_time: 1 },
+            TimedEdge { from: 2, to: 3, capacity: 1, transit_time: 1 },
+            TimedEdge { from: 1, to: 3, capacity: 1, transit_time: 3 },
+        ];
+
+        assert_eq!(max_evacuated(&graph, 1, 3, 100, 1), 0);
+        assert_eq!(max_evacuated(&graph, 1, 3, 100, 2), 1);
+        assert_eq!(max_evacuated(&graph, 1, 3, 100, 3), 3);
+        assert_eq!(max_evacuated(&graph, 1, 3, 2, 3), 2);
+        assert_eq!(quickest_evacuation(&graph, 1, 3, 5), Some(4));
+        assert_eq!(quickest_evacuation(&graph, 1, 3, 0), Some(0));
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let graph: Vec<TimedEdge> = vec![
+            TimedEdge { from: 1, to: 2, capacity: 2, transit_time: 1 },
+            TimedEdge { from: 3, to: 2, capacity: 1, transit_time: 1 },
+        ];
+
+        assert_eq!(max_evacuated(&graph, 1, 3, 10, 20), 0);
+        assert_eq!(quickest_evacuation(&graph, 1, 3, 10), None);
+    }
+
+    #[test]
+    fn test_many_verts() {
+        // 1101 vertices for 101 steps are more copies than vertex ids
+        let mut graph = vec![TimedEdge { from: 1, to: 2, capacity: 1u32, transit_time: 1 }];
+        for vert in 3..1102 {
+            graph.push(TimedEdge { from: vert, to: vert, capacity: 1, transit_time: 1 });
+        }
+
+        assert_eq!(max_evacuated(&graph, 1, 2, 40, 39), 39);
+        assert_eq!(max_evacuated(&graph, 1, 2, 200, 100), 100);
+        assert_eq!(quickest_evacuation(&graph, 1, 2, 100), Some(100));
+    }
+
+    #[test]
+    fn test_rnd() {
+        let mut rng = thread_rng();
+        for _ in 0..200 {
+            let people: u32 = rng.gen_range(0, 30);
+            let instant = rng.gen();
+            let edges: Vec<TimedEdge<u32>> = gen_edges(6, 12, 5).iter()
+                .map(|edge| TimedEdge {
+                    from: edge.from,
+                    to: edge.to,
+                    capacity: edge.capacity,
+                    transit_time: if instant { 0 } else { rng.gen_range(0, 4) },
+                })
+                .collect();
+
+            let quickest = quickest_evacuation(&edges, 1, 2, people);
+
+            let mut evacuated = 0;
+            for deadline in 0..8 {
+                let next = max_evacuated(&edges, 1, 2, people, deadline);
+                assert!(evacuated <= next && next <= people as u64, "edges {:?}", edges);
+                assert_eq!(next >= people as u64, quickest.is_some_and(|quickest| quickest <= deadline), "edges {:?}", edges);
+                if instant {
+                    let plain_edges: Vec<Edge<u32>> = edges.iter()
+                        .map(|edge| Edge { from: edge.from, to: edge.to, capacity: edge.capacity })
+                        .collect();
+                    let per_step = FlowNetwork::new(&plain_edges).max_flow(1, 2);
+                    assert_eq!(next, (people as u64).min(per_step * (deadline as u64 + 1)), "edges {:?}", edges);
+                }
+                evacuated = next;
+            }
+        }
+    }
+}
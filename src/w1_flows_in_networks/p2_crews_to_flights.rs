@@ -1,62 +1,120 @@
 use std::collections::HashMap;
-use w1_flows_in_networks::max_flow;
-use w1_flows_in_networks::Edge;
-use w1_flows_in_networks::VertId;
 use std::collections::HashSet;
+use w1_flows_in_networks::b_matching::max_b_matching;
+use w1_flows_in_networks::hopcroft_karp::max_matching;
+use w1_flows_in_networks::hungarian::MinCostMatching;
+use w1_flows_in_networks::hungarian::min_cost_matching;
 
 pub fn crew_to_flight(crew_to_possible_flights: HashMap<u16, Vec<u16>>) -> HashMap<u16, u16> {
-    let source_vert_id = 0u16;
-
-    let max_crew = crew_to_possible_flights.keys()
-        .map(|k| *k)
-        .max().unwrap();
-
-    let max_flight: u16 = crew_to_possible_flights.values()
-        .map(|flights| if flights.is_empty() {
-            0u16
-        } else {
-            *flights.iter().max().unwrap()
-        })
-        .max().unwrap();
-
-    let flight_as_vert_id = |flight: u16| max_crew + flight;
-    let vert_id_as_flight = |vert_id: VertId| vert_id - max_crew;
-
-    let target_vert_id = flight_as_vert_id(max_flight) + 1;
-
-    let mut edges = Vec::new();
-    let mut flights_connected_to_target = HashSet::new();
-    for (&crew, possible_flights) in crew_to_possible_flights.iter() {
-        if !possible_flights.is_empty() {
-            edges.push(Edge { from: source_vert_id, to: crew, capacity: 1 });
-            for &possible_flight in possible_flights {
-                edges.push(Edge { from: crew, to: flight_as_vert_id(possible_flight), capacity: 1 });
-                if !flights_connected_to_target.contains(&possible_flight) {
-                    edges.push(Edge { from: flight_as_vert_id(possible_flight), to: target_vert_id, capacity: 1 });
-                    flights_connected_to_target.insert(possible_flight);
+    max_matching(&crew_to_possible_flights)
+}
+
+#[derive(Debug)]
+pub struct Deficiency {
+    /// crews reachable from unassigned crews by alternating paths, they have fewer possible flights than them
+    pub crews: HashSet<u16>,
+    /// all flights the `crews` can take, every one is taken by one of them
+    pub flights: HashSet<u16>,
+    /// König minimum vertex cover: every possible pair has a crew or a flight in it,
+    /// and its size is the number of assigned crews
+    pub cover_crews: HashSet<u16>,
+    pub cover_flights: HashSet<u16>,
+}
+
+/// Maximum assignment together with why it is maximum. If some crews are unassigned,
+/// `crews` of the deficiency can take only `flights`, which are fewer than them (Hall's theorem).
+pub fn explained_crew_to_flight(crew_to_possible_flights: &HashMap<u16, Vec<u16>>) -> (HashMap<u16, u16>, Deficiency) {
+    let crew_to_flight = max_matching(crew_to_possible_flights);
+    let flight_to_crew: HashMap<u16, u16> = crew_to_flight.iter().map(|(&crew, &flight)| (flight, crew)).collect();
+
+    // alternating paths go from crews by any possible flight and from flights by their assigned crew
+    let mut crews: HashSet<u16> = crew_to_possible_flights.keys()
+        .filter(|crew| !crew_to_flight.contains_key(crew))
+        .cloned()
+        .collect();
+    let mut flights = HashSet::new();
+    let mut crews_to_visit: Vec<u16> = crews.iter().cloned().collect();
+    while let Some(crew) = crews_to_visit.pop() {
+        for &flight in &crew_to_possible_flights[&crew] {
+            if flights.insert(flight) {
+                let next_crew = flight_to_crew[&flight];
+                if crews.insert(next_crew) {
+                    crews_to_visit.push(next_crew);
                 }
             }
         }
     }
 
-    let vert_id_to_flows = max_flow(&edges, source_vert_id, target_vert_id);
+    let cover_crews = crew_to_flight.keys().filter(|crew| !crews.contains(crew)).cloned().collect();
+    let cover_flights = flights.clone();
+    (crew_to_flight, Deficiency { crews, flights, cover_crews, cover_flights })
+}
+
+/// Assigns as many crews as possible, and the cheapest way among those.
+/// Takes crew -> possible flight -> cost of the crew flying it.
+pub fn cheapest_crew_to_flight(crew_to_flight_costs: &HashMap<u16, HashMap<u16, i64>>) -> MinCostMatching {
+    min_cost_matching(crew_to_flight_costs)
+}
+
+#[derive(Debug)]
+pub struct Staffing {
+    /// crew -> flights it serves
+    pub crew_to_flights: HashMap<u16, Vec<u16>>,
+    /// flight -> how many more crews it needs
+    pub understaffed: HashMap<u16, u16>,
+}
+
+/// Assigns every crew to at most `crew_capacities[crew]` flights and every flight at most
+/// `flight_requirements[flight]` crews, as many assignments as possible.
+/// Crews and flights not in the capacities or requirements take one flight or need one crew.
+pub fn crews_to_flights(crew_to_possible_flights: &HashMap<u16, Vec<u16>>, crew_capacities: &HashMap<u16, u16>,
+                        flight_requirements: &HashMap<u16, u16>) -> Staffing {
+    let mut crews: Vec<u16> = crew_to_possible_flights.keys().cloned().collect();
+    crews.sort();
+    let mut flights: Vec<u16> = crew_to_possible_flights.values()
+        .flat_map(|flights| flights.iter().cloned())
+        .chain(flight_requirements.keys().cloned())
+        .collect();
+    flights.sort();
+    flights.dedup();
+    let required = |flight: u16| flight_requirements.get(&flight).cloned().unwrap_or(1);
 
-    let mut crew_to_flight = HashMap::new();
-    for &crew in crew_to_possible_flights.keys() {
-        let crew_flows_opt = vert_id_to_flows.get(&crew);
-        if crew_flows_opt.is_some() {
-            let flight_vert_id = crew_flows_opt.unwrap().keys().next().unwrap();
-            let flight = vert_id_as_flight(*flight_vert_id);
-            crew_to_flight.insert(crew, flight);
+    // crews are nodes 0..crews.len(), flights follow them
+    let mut pairs = Vec::new();
+    for (crew_i, &crew) in crews.iter().enumerate() {
+        let mut possible_flights = crew_to_possible_flights[&crew].clone();
+        possible_flights.sort();
+        possible_flights.dedup();
+        for flight in possible_flights {
+            pairs.push((crew_i, crews.len() + flights.binary_search(&flight).unwrap()));
         }
     }
-    crew_to_flight
-}
+    let mut node_capacities: Vec<u16> = crews.iter()
+        .map(|crew| crew_capacities.get(crew).cloned().unwrap_or(1))
+        .chain(flights.iter().map(|&flight| required(flight)))
+        .collect();
 
+    let assigned = max_b_matching(&pairs, crews.len(), &mut node_capacities);
+
+    let mut crew_to_flights: HashMap<u16, Vec<u16>> = HashMap::new();
+    for (&(crew_i, flight_node), _) in pairs.iter().zip(&assigned).filter(|&(_, &assigned)| assigned) {
+        crew_to_flights.entry(crews[crew_i]).or_default().push(flights[flight_node - crews.len()]);
+    }
+    let understaffed = flights.iter().enumerate()
+        .map(|(flight_i, &flight)| (flight, node_capacities[crews.len() + flight_i]))
+        .filter(|&(_, missing)| missing > 0)
+        .collect();
+
+    Staffing { crew_to_flights, understaffed }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::Rng;
+    use rand::thread_rng;
+    use w1_flows_in_networks::Edge;
+    use w1_flows_in_networks::FlowNetwork;
 
     #[test]
     fn test1() {
@@ -84,4 +142,162 @@ mod tests {
         assert!(crew_to_flight.get(&1) == Some(&2));
         assert_eq!(crew_to_flight.get(&2), Some(&1));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_deficiency() {
+        let mut crew_to_possible_flights = HashMap::new();
+        crew_to_possible_flights.insert(1, vec![1]);
+        crew_to_possible_flights.insert(2, vec![1]);
+        crew_to_possible_flights.insert(3, vec![1, 2]);
+        crew_to_possible_flights.insert(4, vec![3]);
+
+        let (crew_to_flight, deficiency) = explained_crew_to_flight(&crew_to_possible_flights);
+
+        assert_eq!(crew_to_flight.len(), 3);
+        assert_eq!(crew_to_flight.get(&3), Some(&2));
+        assert_eq!(crew_to_flight.get(&4), Some(&3));
+        assert_eq!(deficiency.crews, [1, 2].iter().cloned().collect());
+        assert_eq!(deficiency.flights, [1].iter().cloned().collect());
+        assert_eq!(deficiency.cover_crews, [3, 4].iter().cloned().collect());
+        assert_eq!(deficiency.cover_flights, [1].iter().cloned().collect());
+    }
+
+    #[test]
+    fn test_deficiency_rnd() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let mut crew_to_possible_flights: HashMap<u16, Vec<u16>> = HashMap::new();
+            for crew in 1..rng.gen_range(1, 9) {
+                let flights = (0..rng.gen_range(0, 4)).map(|_| rng.gen_range(1, 7)).collect();
+                crew_to_possible_flights.insert(crew, flights);
+            }
+
+            let (crew_to_flight, deficiency) = explained_crew_to_flight(&crew_to_possible_flights);
+
+            let all_assigned = crew_to_flight.len() == crew_to_possible_flights.len();
+            assert_eq!(deficiency.flights.len() < deficiency.crews.len(), !all_assigned, "{:?}", crew_to_possible_flights);
+            for crew in &deficiency.crews {
+                assert!(crew_to_possible_flights[crew].iter().all(|flight| deficiency.flights.contains(flight)));
+            }
+            assert_eq!(deficiency.cover_crews.len() + deficiency.cover_flights.len(), crew_to_flight.len());
+            for (crew, flights) in &crew_to_possible_flights {
+                for flight in flights {
+                    assert!(deficiency.cover_crews.contains(crew) || deficiency.cover_flights.contains(flight),
+                            "crew {} and flight {} are not covered, {:?}", crew, flight, crew_to_possible_flights);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cheapest() {
+        let mut crew_to_flight_costs: HashMap<u16, HashMap<u16, i64>> = HashMap::new();
+        crew_to_flight_costs.entry(1).or_default().insert(1, 3);
+        crew_to_flight_costs.entry(1).or_default().insert(2, 5);
+        crew_to_flight_costs.entry(2).or_default().insert(1, 1);
+        crew_to_flight_costs.entry(2).or_default().insert(2, 4);
+
+        let assignment = cheapest_crew_to_flight(&crew_to_flight_costs);
+
+        assert_eq!(assignment.matching.get(&1), Some(&2));
+        assert_eq!(assignment.matching.get(&2), Some(&1));
+        assert_eq!(assignment.cost, 6);
+    }
+
+    #[test]
+    fn test_multiple_crews_and_flights() {
+        let mut crew_to_possible_flights = HashMap::new();
+        crew_to_possible_flights.insert(1, vec![2, 3]);
+        crew_to_possible_flights.insert(2, vec![1]);
+        crew_to_possible_flights.insert(3, vec![3]);
+        let crew_capacities = [(1, 2)].iter().cloned().collect();
+        let flight_requirements = [(1, 2), (3, 3)].iter().cloned().collect();
+
+        let mut staffing = crews_to_flights(&crew_to_possible_flights, &crew_capacities, &flight_requirements);
+
+        for flights in staffing.crew_to_flights.values_mut() {
+            flights.sort();
+        }
+        assert_eq!(staffing.crew_to_flights.get(&1), Some(&vec![2, 3]));
+        assert_eq!(staffing.crew_to_flights.get(&2), Some(&vec![1]));
+        assert_eq!(staffing.crew_to_flights.get(&3), Some(&vec![3]));
+        assert_eq!(staffing.understaffed, [(1, 1), (3, 1)].iter().cloned().collect());
+    }
+
+    #[test]
+    fn test_multiple_crews_and_flights_rnd() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let mut crew_to_possible_flights: HashMap<u16, Vec<u16>> = HashMap::new();
+            let mut crew_capacities = HashMap::new();
+            for crew in 1..rng.gen_range(1, 9) {
+                let flights = (0..rng.gen_range(0, 5)).map(|_| rng.gen_range(1, 9)).collect();
+                crew_to_possible_flights.insert(crew, flights);
+                crew_capacities.insert(crew, rng.gen_range(0, 4));
+            }
+            let flight_requirements: HashMap<u16, u16> = (1..9).map(|flight| (flight, rng.gen_range(0, 4))).collect();
+
+            let staffing = crews_to_flights(&crew_to_possible_flights, &crew_capacities, &flight_requirements);
+
+            let mut flight_to_crews_count: HashMap<u16, u16> = HashMap::new();
+            let mut assigned = 0;
+            for (crew, flights) in &staffing.crew_to_flights {
+                assert!(flights.len() as u16 <= crew_capacities[crew]);
+                for flight in flights {
+                    assert!(crew_to_possible_flights[crew].contains(flight));
+                    *flight_to_crews_count.entry(*flight).or_insert(0) += 1;
+                    assigned += 1;
+                }
+            }
+            for (flight, &required) in &flight_requirements {
+                let count = flight_to_crews_count.get(flight).cloned().unwrap_or(0);
+                assert_eq!(staffing.understaffed.get(flight).cloned().unwrap_or(0), required - count);
+            }
+
+            // crews are 1..=8, flights 11..=18, source 0 and sink 20
+            let mut edges = Vec::new();
+            for (&crew, flights) in &crew_to_possible_flights {
+                edges.push(Edge { from: 0, to: crew, capacity: crew_capacities[&crew] });
+                let flights: HashSet<&u16> = flights.iter().collect();
+                for &flight in flights {
+                    edges.push(Edge { from: crew, to: flight + 10, capacity: 1 });
+                }
+            }
+            for (&flight, &required) in &flight_requirements {
+                edges.push(Edge { from: flight + 10, to: 20, capacity: required });
+            }
+            let expected = FlowNetwork::new(&edges).max_flow(0, 20);
+            assert_eq!(assigned, expected, "crews {:?}, capacities {:?}, requirements {:?}",
+                       crew_to_possible_flights, crew_capacities, flight_requirements);
+        }
+    }
+
+    #[test]
+    fn test_airline_scale() {
+        // 20k crews and 50k flights do not fit into one u16 vertex id space together
+        let mut rng = thread_rng();
+        let mut crew_to_possible_flights: HashMap<u16, Vec<u16>> = HashMap::new();
+        for crew in 0..20_000 {
+            crew_to_possible_flights.insert(crew, (0..3).map(|_| rng.gen_range(0, 50_000)).collect());
+        }
+        let flight_requirements: HashMap<u16, u16> = (0..50_000).map(|flight| (flight, 1)).collect();
+
+        let staffing = crews_to_flights(&crew_to_possible_flights, &HashMap::new(), &flight_requirements);
+
+        let assigned: usize = staffing.crew_to_flights.values().map(|flights| flights.len()).sum();
+        assert_eq!(assigned, max_matching(&crew_to_possible_flights).len());
+        assert_eq!(staffing.understaffed.len(), 50_000 - assigned);
+    }
+
+    #[test]
+    fn test_unknown_flight() {
+        let mut crew_to_possible_flights = HashMap::new();
+        crew_to_possible_flights.insert(1, vec![1]);
+        let flight_requirements = [(7, 2)].iter().cloned().collect();
+
+        let staffing = crews_to_flights(&crew_to_possible_flights, &HashMap::new(), &flight_requirements);
+
+        assert_eq!(staffing.crew_to_flights.get(&1), Some(&vec![1]));
+        assert_eq!(staffing.understaffed, [(7, 2)].iter().cloned().collect());
+    }
+}
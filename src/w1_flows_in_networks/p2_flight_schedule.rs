@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use w1_flows_in_networks::p2_crews_to_flights::crew_to_flight;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flight {
+    pub origin: u16,
+    pub destination: u16,
+    pub departure: u32,
+    pub arrival: u32,
+}
+
+/// Flight -> flights the same crew can fly next: they depart from its destination not earlier than it arrives.
+/// Flights are identified by their positions in `flights`. The graph has no cycles as every flight arrives
+/// after it departs.
+pub fn can_follow(flights: &[Flight]) -> HashMap<u16, Vec<u16>> {
+    assert!(flights.len() <= u16::MAX as usize, "too many flights: {}", flights.len());
+    for (flight_i, flight) in flights.iter().enumerate() {
+        assert!(flight.departure < flight.arrival, "flight {} does not arrive after it departs: {:?}", flight_i, flight);
+    }
+
+    // origin -> flights from it by departure
+    let mut origin_to_flights: HashMap<u16, Vec<u16>> = HashMap::new();
+    for (flight_i, flight) in flights.iter().enumerate() {
+        origin_to_flights.entry(flight.origin).or_default().push(flight_i as u16);
+    }
+    for departing in origin_to_flights.values_mut() {
+        departing.sort_by_key(|&flight_i| flights[flight_i as usize].departure);
+    }
+
+    flights.iter().enumerate()
+        .map(|(flight_i, flight)| {
+            let next_flights = origin_to_flights.get(&flight.destination).map_or_else(Vec::new, |departing| {
+                let first = departing.partition_point(|&next_i| flights[next_i as usize].departure < flight.arrival);
+                departing[first..].to_vec()
+            });
+            (flight_i as u16, next_flights)
+        })
+        .collect()
+}
+
+/// Itineraries of the fewest crews which fly every flight, each is a list of positions in `flights`.
+/// Minimum path cover of the "can follow" graph: assigning every flight the flight flown next by the same crew
+/// is a matching between flights and their copies, and every matched pair saves a crew.
+pub fn min_crews_itineraries(flights: &[Flight]) -> Vec<Vec<u16>> {
+    let next_flight = crew_to_flight(can_follow(flights));
+    let followers: HashSet<u16> = next_flight.values().cloned().collect();
+
+    (0..flights.len() as u16)
+        .filter(|flight| !followers.contains(flight))
+        .map(|first_flight| {
+            let mut itinerary = vec![first_flight];
+            while let Some(&flight) = next_flight.get(itinerary.last().unwrap()) {
+                itinerary.push(flight);
+            }
+            itinerary
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::thread_rng;
+    use w1_flows_in_networks::Edge;
+    use w1_flows_in_networks::FlowNetwork;
+
+    #[test]
+    fn test1() {
+        let flights = vec![
+            Flight { origin: 1, destination: 2, departure: 8, arrival: 10 },
+            Flight { origin: 2, destination: 1, departure: 11, arrival: 13 },
+            Flight { origin: 2, destination: 3, departure: 9, arrival: 12 },
+            Flight { origin: 1, destination: 3, departure: 13, arrival: 15 },
+            Flight { origin: 3, destination: 2, departure: 12, arrival: 14 },
+        ];
+
+        let mut can_follow = can_follow(&flights);
+        for next_flights in can_follow.values_mut() {
+            next_flights.sort();
+        }
+        assert_eq!(can_follow[&0], vec![1]);
+        assert_eq!(can_follow[&1], vec![3]);
+        assert_eq!(can_follow[&2], vec![4]);
+        assert_eq!(can_follow[&3], vec![]);
+        assert_eq!(can_follow[&4], vec![]);
+
+        let mut itineraries = min_crews_itineraries(&flights);
+        itineraries.sort();
+        assert_eq!(itineraries, vec![vec![0, 1, 3], vec![2, 4]]);
+    }
+
+    #[test]
+    fn test_rnd() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let flights: Vec<Flight> = (0..rng.gen_range(0, 12))
+                .map(|_| {
+                    let departure = rng.gen_range(0, 20);
+                    Flight {
+                        origin: rng.gen_range(1, 4),
+                        destination: rng.gen_range(1, 4),
+                        departure,
+                        arrival: departure + rng.gen_range(1, 5),
+                    }
+                })
+                .collect();
+
+            let can_follow = can_follow(&flights);
+            let itineraries = min_crews_itineraries(&flights);
+
+            for (flight_i, flight) in flights.iter().enumerate() {
+                for (next_i, next) in flights.iter().enumerate() {
+                    let follows = next.origin == flight.destination && next.departure >= flight.arrival;
+                    assert_eq!(can_follow[&(flight_i as u16)].contains(&(next_i as u16)), follows, "flights {:?}", flights);
+                }
+            }
+
+            let mut flown: Vec<u16> = itineraries.iter().flatten().cloned().collect();
+            flown.sort();
+            assert_eq!(flown, (0..flights.len() as u16).collect::<Vec<_>>(), "flights {:?}", flights);
+            for itinerary in &itineraries {
+                for pair in itinerary.windows(2) {
+                    let (flight, next) = (&flights[pair[0] as usize], &flights[pair[1] as usize]);
+                    assert!(flight.destination == next.origin && flight.arrival <= next.departure,
+                            "{:?} can not follow {:?}", next, flight);
+                }
+            }
+
+            // flights are 1..=n, their copies n+1..=2n, source 0 and sink 2n+1
+            let count = flights.len() as u16;
+            let mut edges = Vec::new();
+            for (&flight, next_flights) in &can_follow {
+                edges.push(Edge { from: 0, to: flight + 1, capacity: 1 });
+                edges.push(Edge { from: flight + count + 1, to: 2 * count + 1, capacity: 1 });
+                for &next in next_flights {
+                    edges.push(Edge { from: flight + 1, to: next + count + 1, capacity: 1 });
+                }
+            }
+            let expected = flights.len() - FlowNetwork::new(&edges).max_flow(0, 2 * count + 1) as usize;
+            assert_eq!(itineraries.len(), expected, "flights {:?}", flights);
+        }
+    }
+}
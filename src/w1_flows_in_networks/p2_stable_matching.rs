@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+/// crew or flight -> groups of equally good partners, best group first.
+/// Partners not listed are not acceptable.
+pub type Preferences = HashMap<u16, Vec<Vec<u16>>>;
+
+/// Side which proposes. With strict preferences every one of it gets the best partner it has in any stable matching.
+/// With ties that holds only for the preferences after ties are broken, not among all stable matchings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Optimal {
+    Crews,
+    Flights,
+}
+
+/// Gale-Shapley: matching where no crew and flight both strictly prefer each other to their partners or to nobody.
+/// A crew and a flight can be matched only if both list each other.
+/// Ties are broken by the order inside a group, which keeps the matching stable for the original preferences.
+pub fn stable_crew_to_flight(crew_preferences: &Preferences, flight_preferences: &Preferences, optimal: Optimal)
+                             -> HashMap<u16, u16> {
+    match optimal {
+        Optimal::Crews => gale_shapley(crew_preferences, flight_preferences),
+        Optimal::Flights => gale_shapley(flight_preferences, crew_preferences).into_iter()
+            .map(|(flight, crew)| (crew, flight))
+            .collect(),
+    }
+}
+
+/// Free proposers propose in order of their preferences, a receiver keeps the best proposal so far.
+/// Returns proposer -> receiver.
+fn gale_shapley(proposer_preferences: &Preferences, receiver_preferences: &Preferences) -> HashMap<u16, u16> {
+    // receiver -> proposer -> rank, lower is better
+    let receiver_ranks: HashMap<u16, HashMap<u16, usize>> = receiver_preferences.iter()
+        .map(|(&receiver, groups)| (receiver, groups.iter().flatten().enumerate().map(|(rank, &proposer)| (proposer, rank)).collect()))
+        .collect();
+    let rank = |receiver: u16, proposer: u16| receiver_ranks.get(&receiver).and_then(|ranks| ranks.get(&proposer)).cloned();
+
+    let mut proposer_to_next: HashMap<u16, usize> = HashMap::new();
+    let mut receiver_to_proposer: HashMap<u16, u16> = HashMap::new();
+    let mut free_proposers: VecDeque<u16> = proposer_preferences.keys().cloned().collect();
+    while let Some(proposer) = free_proposers.pop_front() {
+        let receivers: Vec<u16> = proposer_preferences[&proposer].iter().flatten().cloned().collect();
+        let next = proposer_to_next.entry(proposer).or_insert(0);
+        while *next < receivers.len() {
+            let receiver = receivers[*next];
+            *next += 1;
+            let proposer_rank = match rank(receiver, proposer) {
+                Some(proposer_rank) => proposer_rank,
+                None => continue,
+            };
+            match receiver_to_proposer.get(&receiver).cloned() {
+                None => {
+                    receiver_to_proposer.insert(receiver, proposer);
+                    break;
+                }
+                Some(held) if proposer_rank < rank(receiver, held).unwrap() => {
+                    receiver_to_proposer.insert(receiver, proposer);
+                    free_proposers.push_back(held);
+                    break;
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    receiver_to_proposer.into_iter().map(|(receiver, proposer)| (proposer, receiver)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand::thread_rng;
+
+    #[test]
+    fn test1() {
+        let crew_preferences: Preferences = [
+            (1, vec![vec![1], vec![2], vec![3]]),
+            (2, vec![vec![2], vec![1], vec![3]]),
+            (3, vec![vec![1], vec![2], vec![3]]),
+        ].iter().cloned().collect();
+        let flight_preferences: Preferences = [
+            (1, vec![vec![2], vec![1], vec![3]]),
+            (2, vec![vec![1], vec![2], vec![3]]),
+            (3, vec![vec![1], vec![2], vec![3]]),
+        ].iter().cloned().collect();
+
+        let crews_optimal = stable_crew_to_flight(&crew_preferences, &flight_preferences, Optimal::Crews);
+        let flights_optimal = stable_crew_to_flight(&crew_preferences, &flight_preferences, Optimal::Flights);
+
+        assert_eq!(crews_optimal, [(1, 1), (2, 2), (3, 3)].iter().cloned().collect());
+        assert_eq!(flights_optimal, [(1, 2), (2, 1), (3, 3)].iter().cloned().collect());
+    }
+
+    #[test]
+    fn test_incomplete_lists() {
+        let crew_preferences: Preferences = [
+            (1, vec![vec![1]]),
+            (2, vec![vec![1, 2]]),
+        ].iter().cloned().collect();
+        let flight_preferences: Preferences = [
+            (1, vec![vec![2]]),
+            (2, vec![vec![1]]),
+        ].iter().cloned().collect();
+
+        let matching = stable_crew_to_flight(&crew_preferences, &flight_preferences, Optimal::Crews);
+
+        assert_eq!(matching, [(2, 1)].iter().cloned().collect());
+    }
+
+    #[test]
+    fn test_rnd() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let ties = rng.gen();
+            let crew_preferences = gen_preferences(&mut rng, ties);
+            let flight_preferences = gen_preferences(&mut rng, ties);
+
+            let crews_optimal = stable_crew_to_flight(&crew_preferences, &flight_preferences, Optimal::Crews);
+            let flights_optimal = stable_crew_to_flight(&crew_preferences, &flight_preferences, Optimal::Flights);
+
+            check_stable(&crew_preferences, &flight_preferences, &crews_optimal);
+            check_stable(&crew_preferences, &flight_preferences, &flights_optimal);
+            if !ties {
+                let flights_optimal_flight_to_crew = flight_to_crew(&flights_optimal);
+                let crews_optimal_flight_to_crew = flight_to_crew(&crews_optimal);
+                for &crew in crew_preferences.keys() {
+                    assert!(rank(&crew_preferences, crew, crews_optimal.get(&crew))
+                                <= rank(&crew_preferences, crew, flights_optimal.get(&crew)),
+                            "crew {}, crews {:?}, flights {:?}", crew, crew_preferences, flight_preferences);
+                }
+                for &flight in flight_preferences.keys() {
+                    assert!(rank(&flight_preferences, flight, flights_optimal_flight_to_crew.get(&flight))
+                                <= rank(&flight_preferences, flight, crews_optimal_flight_to_crew.get(&flight)),
+                            "flight {}, crews {:?}, flights {:?}", flight, crew_preferences, flight_preferences);
+                }
+            }
+        }
+    }
+
+    /// Up to 6 crews or flights, each lists some of 1..=6 in up to 3 groups, or one by one without ties
+    fn gen_preferences<R: Rng>(rng: &mut R, ties: bool) -> Preferences {
+        let mut preferences = HashMap::new();
+        for vert in 1..rng.gen_range(1, 8) {
+            let mut partners: Vec<u16> = (1..7).filter(|_| rng.gen()).collect();
+            rng.shuffle(&mut partners);
+            if !ties {
+                preferences.insert(vert, partners.iter().map(|&partner| vec![partner]).collect());
+                continue;
+            }
+            let mut groups: Vec<Vec<u16>> = vec![Vec::new(); 3];
+            for partner in partners {
+                groups[rng.gen_range(0, 3)].push(partner);
+            }
+            groups.retain(|group| !group.is_empty());
+            preferences.insert(vert, groups);
+        }
+        preferences
+    }
+
+    fn flight_to_crew(matching: &HashMap<u16, u16>) -> HashMap<u16, u16> {
+        matching.iter().map(|(&crew, &flight)| (flight, crew)).collect()
+    }
+
+    /// Group of the partner in the preferences, being unmatched is worse than any group
+    fn rank(preferences: &Preferences, vert: u16, partner: Option<&u16>) -> usize {
+        partner.map_or(usize::MAX, |partner| preferences[&vert].iter().position(|group| group.contains(partner)).unwrap())
+    }
+
+    fn check_stable(crew_preferences: &Preferences, flight_preferences: &Preferences, matching: &HashMap<u16, u16>) {
+        let group = |preferences: &Preferences, vert: u16, partner: u16| {
+            preferences.get(&vert).and_then(|groups| groups.iter().position(|group| group.contains(&partner)))
+        };
+        let flight_to_crew: HashMap<u16, u16> = matching.iter().map(|(&crew, &flight)| (flight, crew)).collect();
+        assert_eq!(flight_to_crew.len(), matching.len(), "a flight is matched twice: {:?}", matching);
+
+        for (&crew, &flight) in matching {
+            assert!(group(crew_preferences, crew, flight).is_some() && group(flight_preferences, flight, crew).is_some(),
+                    "crew {} and flight {} do not accept each other", crew, flight);
+        }
+        for (&crew, groups) in crew_preferences {
+            for &flight in groups.iter().flatten() {
+                let flight_group = match group(flight_preferences, flight, crew) {
+                    Some(flight_group) => flight_group,
+                    None => continue,
+                };
+                let crew_prefers = matching.get(&crew)
+                    .is_none_or(|&current| group(crew_preferences, crew, flight) < group(crew_preferences, crew, current));
+                let flight_prefers = flight_to_crew.get(&flight)
+                    .is_none_or(|&current| Some(flight_group) < group(flight_preferences, flight, current));
+                assert!(!(crew_prefers && flight_prefers), "crew {} and flight {} block {:?}, crews {:?}, flights {:?}",
+                        crew, flight, matching, crew_preferences, flight_preferences);
+            }
+        }
+    }
+}
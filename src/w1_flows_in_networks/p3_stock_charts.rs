@@ -1,6 +1,7 @@
 use w1_flows_in_networks::Edge;
+use std::collections::HashMap;
 use std::collections::HashSet;
-use w1_flows_in_networks::max_flow;
+use w1_flows_in_networks::FlowNetwork;
 use w1_flows_in_networks::VertId;
 
 type Stock<'a> = &'a[i32];
@@ -19,6 +20,8 @@ pub fn charts<'a>(stocks: &[Stock<'a>]) -> Vec<Chart<'a>> {
 
         let source_vert_id = (left_charts.len() + right_charts.len()) as VertId;
         let mut edges: Vec<Edge> = Vec::new();
+        // (index of edge in edges, left chart index, right chart index)
+        let mut combine_edges = Vec::new();
         for left_chart_i in 0..left_charts.len() {
             let mut need_edge_from_source = false;
 
@@ -29,6 +32,7 @@ pub fn charts<'a>(stocks: &[Stock<'a>]) -> Vec<Chart<'a>> {
 
                     // TODO: reuse logic
                     let right_vert_id = (left_charts.len() + right_chart_i) as u16;
+                    combine_edges.push((edges.len(), left_chart_i, right_chart_i));
                     edges.push(create_edge(left_chart_i as u16, right_vert_id));
                 }
             }
@@ -45,9 +49,14 @@ pub fn charts<'a>(stocks: &[Stock<'a>]) -> Vec<Chart<'a>> {
             edges.push(create_edge(right_vert_id, target_vert_id));
         }
 
-        let vert_id_to_flows = max_flow(&edges, source_vert_id, target_vert_id);
+        let mut network = FlowNetwork::new(&edges);
+        let flow_amount = network.max_flow(source_vert_id, target_vert_id);
+        let left_to_right_chart: HashMap<usize, usize> = combine_edges.iter()
+            .filter(|&&(edge_i, _, _)| network.flow(edge_i) > 0)
+            .map(|&(_, left_chart_i, right_chart_i)| (left_chart_i, right_chart_i))
+            .collect();
 
-        if vert_id_to_flows.is_empty() {
+        if flow_amount == 0 {
             // TODO: remove last index, not first
             final_charts.push(left_charts.remove(0));
             left_charts.extend_from_slice(&right_charts);
@@ -58,7 +67,7 @@ pub fn charts<'a>(stocks: &[Stock<'a>]) -> Vec<Chart<'a>> {
             left_charts = left_and_right_charts.0;
             right_charts = left_and_right_charts.1;
 
-        } else if vert_id_to_flows.get(&0).is_none() {
+        } else if !left_to_right_chart.contains_key(&0) {
             left_charts.extend_from_slice(&right_charts);
             // TODO: pass base chart explicitly
             let left_and_right_charts = split_charts(left_charts);
@@ -69,11 +78,7 @@ pub fn charts<'a>(stocks: &[Stock<'a>]) -> Vec<Chart<'a>> {
             let mut new_left_charts = Vec::new();
             let mut combined_right_charts = HashSet::new();
             for left_chart_i in 0..left_charts.len() {
-                if let Some(right_vert_id_to_flow) = vert_id_to_flows.get(&(left_chart_i as u16)) {
-                    let &right_vert_id = right_vert_id_to_flow.keys().into_iter().next().unwrap();
-                    // TODO: encapsulate
-                    let right_chart_i = right_vert_id as usize - left_charts.len();
-
+                if let Some(&right_chart_i) = left_to_right_chart.get(&left_chart_i) {
                     // TODO: can we do it without clone?
                     let mut new_chart = left_charts[left_chart_i].clone();
 
@@ -0,0 +1,247 @@
+use std::collections::VecDeque;
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::Flows;
+use w1_flows_in_networks::VertId;
+use w1_flows_in_networks::add_capacities;
+use w1_flows_in_networks::sub_capacities;
+use w1_flows_in_networks::FlowNetwork;
+
+const NO_DIST: usize = usize::MAX;
+
+/// Same result as `w1_flows_in_networks::max_flow`, computed by FIFO preflow-push in O(V^3) time.
+/// Suits dense graphs better than augmenting paths.
+pub fn max_flow<C: Capacity>(edges: &[Edge<C>], from: VertId, to: VertId) -> Flows<C::Sum> {
+    let mut graph = FlowNetwork::new(edges);
+    graph.add_vert(from.max(to));
+    if from != to {
+        // excess returned to the source through its entering edges would only make cycles
+        let entering_arcs: Vec<usize> = graph.out_arcs[from as usize].iter()
+            .filter(|&&arc| arc % 2 == 1)
+            .map(|&arc| arc ^ 1)
+            .collect();
+        for arc in entering_arcs {
+            graph.capacities[arc] = C::zero();
+        }
+        PushRelabel::new(&mut graph, from as usize, to as usize).run();
+    }
+    graph.flows()
+}
+
+struct PushRelabel<'a, C: 'a + Capacity> {
+    graph: &'a mut FlowNetwork<C>,
+    source: usize,
+    sink: usize,
+    heights: Vec<usize>,
+    /// vertex -> excess, wider than capacities as it collects flow from many arcs
+    excesses: Vec<C::Sum>,
+    /// height -> number of vertices with that height, to find gaps
+    height_counts: Vec<usize>,
+    /// vertex -> index of the first out arc that may be admissible
+    next_arcs: Vec<usize>,
+    active_verts: VecDeque<usize>,
+}
+
+impl<'a, C: Capacity> PushRelabel<'a, C> {
+    fn new(graph: &'a mut FlowNetwork<C>, source: usize, sink: usize) -> PushRelabel<'a, C> {
+        let verts_count = graph.out_arcs.len();
+        let mut heights = vec![0; verts_count];
+        heights[source] = verts_count;
+        PushRelabel {
+            graph,
+            source,
+            sink,
+            heights,
+            excesses: vec![C::Sum::zero(); verts_count],
+            height_counts: vec![0; 2 * verts_count + 2],
+            next_arcs: vec![0; verts_count],
+            active_verts: VecDeque::new(),
+        }
+    }
+
+    fn run(&mut self) {
+        self.global_relabel();
+
+        for i in 0..self.graph.out_arcs[self.source].len() {
+            let arc = self.graph.out_arcs[self.source][i];
+            let capacity = self.graph.capacities[arc];
+            if capacity > C::zero() {
+                self.push(arc, capacity);
+            }
+        }
+
+        let verts_count = self.heights.len();
+        let mut relabels_count = 0;
+        while let Some(vert) = self.active_verts.pop_front() {
+            relabels_count += self.discharge(vert);
+            if relabels_count >= verts_count {
+                self.global_relabel();
+                relabels_count = 0;
+            }
+        }
+    }
+
+    /// Pushes all excess out of the vertex, returns number of relabels it took
+    fn discharge(&mut self, vert: usize) -> usize {
+        let mut relabels_count = 0;
+        while self.excesses[vert] > C::Sum::zero() {
+            if self.next_arcs[vert] == self.graph.out_arcs[vert].len() {
+                self.relabel(vert);
+                relabels_count += 1;
+                continue;
+            }
+
+            let arc = self.graph.out_arcs[vert][self.next_arcs[vert]];
+            let capacity = self.graph.capacities[arc];
+            if capacity > C::zero() && self.heights[vert] == self.heights[self.graph.heads[arc]] + 1 {
+                let excess = self.excesses[vert];
+                // excess below an arc capacity fits into C
+                let amount = if capacity.to_sum() < excess { capacity } else { C::from_sum(excess).unwrap() };
+                self.push(arc, amount);
+            } else {
+                self.next_arcs[vert] += 1;
+            }
+        }
+        relabels_count
+    }
+
+    fn push(&mut self, arc: usize, amount: C) {
+        let tail = self.graph.tail(arc);
+        let head = self.graph.heads[arc];
+        self.graph.push(arc, amount);
+        if tail != self.source {
+            self.excesses[tail] = sub_capacities(self.excesses[tail], amount.to_sum());
+        }
+        let head_was_active = self.excesses[head] > C::Sum::zero();
+        self.excesses[head] = add_capacities(self.excesses[head], amount.to_sum());
+        if !head_was_active && head != self.source && head != self.sink {
+            self.active_verts.push_back(head);
+        }
+    }
+
+    fn relabel(&mut self, vert: usize) {
+        let verts_count = self.heights.len();
+        let old_height = self.heights[vert];
+        self.height_counts[old_height] -= 1;
+        if self.height_counts[old_height] == 0 && old_height < verts_count {
+            // gap: vertices above it can not reach the sink anymore
+            for other_vert in 0..verts_count {
+                let height = self.heights[other_vert];
+                if height > old_height && height < verts_count {
+                    self.height_counts[height] -= 1;
+                    self.heights[other_vert] = verts_count + 1;
+                    self.height_counts[verts_count + 1] += 1;
+                }
+            }
+        }
+
+        let new_height = self.graph.out_arcs[vert].iter()
+            .filter(|&&arc| self.graph.capacities[arc] > C::zero())
+            .map(|&arc| self.heights[self.graph.heads[arc]] + 1)
+            .min()
+            .unwrap();
+        self.heights[vert] = new_height;
+        self.height_counts[new_height] += 1;
+        self.next_arcs[vert] = 0;
+    }
+
+    /// Sets heights to exact distances to the sink in the residual graph,
+    /// vertices that can not reach the sink are lifted to at least the source height
+    fn global_relabel(&mut self) {
+        let verts_count = self.heights.len();
+        let mut dists = vec![NO_DIST; verts_count];
+        dists[self.sink] = 0;
+        let mut verts = VecDeque::new();
+        verts.push_back(self.sink);
+        while let Some(vert) = verts.pop_front() {
+            for &arc in &self.graph.out_arcs[vert] {
+                let other_vert = self.graph.heads[arc];
+                if dists[other_vert] == NO_DIST && other_vert != self.source
+                    && self.graph.capacities[arc ^ 1] > C::zero() {
+                    dists[other_vert] = dists[vert] + 1;
+                    verts.push_back(other_vert);
+                }
+            }
+        }
+
+        for count in &mut self.height_counts {
+            *count = 0;
+        }
+        for (vert, height) in self.heights.iter_mut().enumerate() {
+            if vert != self.source {
+                *height = if dists[vert] == NO_DIST { (*height).max(verts_count) } else { dists[vert] };
+            }
+            self.height_counts[*height] += 1;
+        }
+        for next_arc in &mut self.next_arcs {
+            *next_arc = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use w1_flows_in_networks;
+    use w1_flows_in_networks::test_utils::check_flows;
+    use w1_flows_in_networks::test_utils::check_max_flow_rnd;
+    use w1_flows_in_networks::test_utils::evacuation_graph;
+    use w1_flows_in_networks::test_utils::flow_amount;
+    use w1_flows_in_networks::test_utils::gen_edges;
+
+    #[test]
+    fn test1() {
+        let flows = max_flow(&evacuation_graph(), 1, 5);
+
+        assert_eq!(flow_amount(&flows, 1), 6);
+    }
+
+    #[test]
+    fn test2() {
+        let graph = vec![
+            Edge { from: 1, to: 2, capacity: 10_000 },
+            Edge { from: 1, to: 3, capacity: 10_000 },
+            Edge { from: 2, to: 3, capacity: 1 },
+            Edge { from: 3, to: 4, capacity: 10_000 },
+            Edge { from: 2, to: 4, capacity: 10_000 },
+        ];
+
+        let expected = flow_amount(&w1_flows_in_networks::max_flow(&graph, 1, 4), 1);
+        let flows = max_flow(&graph, 1, 4);
+
+        check_flows(&graph, &flows, 1, 4);
+        assert_eq!(flow_amount(&flows, 1), expected);
+    }
+
+    #[test]
+    fn test_excess_wider_than_capacities() {
+        let graph = vec![
+            Edge { from: 1, to: 2, capacity: 60_000u16 },
+            Edge { from: 2, to: 4, capacity: 60_000 },
+            Edge { from: 1, to: 3, capacity: 60_000 },
+            Edge { from: 3, to: 4, capacity: 60_000 },
+        ];
+
+        let flows = max_flow(&graph, 1, 4);
+
+        assert_eq!(flow_amount(&flows, 1), 120_000);
+    }
+
+    #[test]
+    fn test_rnd() {
+        check_max_flow_rnd(max_flow);
+    }
+
+    #[test]
+    fn test_rnd_dense() {
+        for _ in 0..100 {
+            let edges = gen_edges(30, 500, 1000);
+
+            let expected = flow_amount(&w1_flows_in_networks::max_flow(&edges, 1, 2), 1);
+            let flows = max_flow(&edges, 1, 2);
+
+            check_flows(&edges, &flows, 1, 2);
+            assert_eq!(flow_amount(&flows, 1), expected, "edges {:?}", edges);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use w1_flows_in_networks::Capacity;
+use w1_flows_in_networks::Edge;
+use w1_flows_in_networks::Flows;
+use w1_flows_in_networks::VertId;
+use w1_flows_in_networks::add_capacities;
+
+#[derive(Debug, PartialEq)]
+pub enum Violation<C> {
+    /// flow from one vertex to another is negative or exceeds total capacity of edges between them
+    Capacity { from: VertId, to: VertId, flow: C, capacity: C },
+    /// a vertex other than the source and the sink does not send what it gets
+    Conservation { vert: VertId, inflow: C, outflow: C },
+    /// the source gets more than it sends
+    NegativeAmount { inflow: C, outflow: C },
+    /// flow can still be increased along these vertices
+    NotMaximal { path: Vec<VertId> },
+}
+
+#[derive(Debug)]
+pub struct FlowReport<C> {
+    /// net flow leaving the source, zero if it is negative
+    pub amount: C,
+    pub violations: Vec<Violation<C>>,
+    /// source side of a cut with capacity equal to the amount, which proves the flow is maximum
+    pub cut: Option<HashSet<VertId>>,
+}
+
+impl<C> FlowReport<C> {
+    pub fn is_max_flow(&self) -> bool {
+        self.violations.is_empty() && self.cut.is_some()
+    }
+}
+
+/// Checks that flows fit into capacities, are conserved and can not be increased.
+/// Flows, like capacities of parallel edges and totals, are of the wider `C::Sum` type.
+/// Floating point amounts are compared up to rounding errors.
+pub fn verify_flow<C: Capacity>(edges: &[Edge<C>], source: VertId, sink: VertId, vert_to_flows: &Flows<C::Sum>)
+                                -> FlowReport<C::Sum> {
+    let mut capacities: HashMap<(VertId, VertId), C::Sum> = HashMap::new();
+    let mut vert_to_neighbours: HashMap<VertId, HashSet<VertId>> = HashMap::new();
+    for edge in edges {
+        if edge.from == edge.to || edge.capacity <= C::zero() {
+            continue;
+        }
+        let capacity = capacities.entry((edge.from, edge.to)).or_insert_with(C::Sum::zero);
+        *capacity = add_capacities(*capacity, edge.capacity.to_sum());
+        vert_to_neighbours.entry(edge.from).or_default().insert(edge.to);
+        vert_to_neighbours.entry(edge.to).or_default().insert(edge.from);
+    }
+    let capacity = |from: VertId, to: VertId| capacities.get(&(from, to)).cloned().unwrap_or_else(C::Sum::zero);
+    let flow = |from: VertId, to: VertId| {
+        vert_to_flows.get(&from).and_then(|flows| flows.get(&to)).cloned().unwrap_or_else(C::Sum::zero)
+    };
+    let less = |a: C::Sum, b: C::Sum| a < b && !a.close_to(b);
+
+    let mut violations = Vec::new();
+    // vertex -> (inflow, outflow)
+    let mut vert_to_balance: HashMap<VertId, (C::Sum, C::Sum)> = HashMap::new();
+    for (&from, flows) in vert_to_flows {
+        for (&to, &amount) in flows {
+            if from == to {
+                continue;
+            }
+            if less(amount, C::Sum::zero()) || less(capacity(from, to), amount) {
+                violations.push(Violation::Capacity { from, to, flow: amount, capacity: capacity(from, to) });
+            }
+            let from_balance = vert_to_balance.entry(from).or_insert((C::Sum::zero(), C::Sum::zero()));
+            from_balance.1 = add_capacities(from_balance.1, amount);
+            let to_balance = vert_to_balance.entry(to).or_insert((C::Sum::zero(), C::Sum::zero()));
+            to_balance.0 = add_capacities(to_balance.0, amount);
+            vert_to_neighbours.entry(from).or_default().insert(to);
+            vert_to_neighbours.entry(to).or_default().insert(from);
+        }
+    }
+    for (&vert, &(inflow, outflow)) in &vert_to_balance {
+        if vert != source && vert != sink && !inflow.close_to(outflow) {
+            violations.push(Violation::Conservation { vert, inflow, outflow });
+        }
+    }
+
+    let (inflow, outflow) = vert_to_balance.get(&source).cloned().unwrap_or((C::Sum::zero(), C::Sum::zero()));
+    let amount = match outflow.checked_sub(inflow) {
+        Some(amount) if amount >= C::Sum::zero() => amount,
+        Some(_) if inflow.close_to(outflow) => C::Sum::zero(),
+        _ => {
+            violations.push(Violation::NegativeAmount { inflow, outflow });
+            C::Sum::zero()
+        }
+    };
+
+    // search for an augmenting path, arc from -> to is residual if its edges are not full or flow goes back
+    let mut parents: HashMap<VertId, VertId> = HashMap::new();
+    let mut reachable: HashSet<VertId> = HashSet::new();
+    reachable.insert(source);
+    let mut verts = VecDeque::new();
+    verts.push_back(source);
+    while let Some(vert) = verts.pop_front() {
+        if let Some(neighbours) = vert_to_neighbours.get(&vert) {
+            for &neighbour in neighbours {
+                if !reachable.contains(&neighbour)
+                    && (less(flow(vert, neighbour), capacity(vert, neighbour)) || less(C::Sum::zero(), flow(neighbour, vert))) {
+                    reachable.insert(neighbour);
+                    parents.insert(neighbour, vert);
+                    verts.push_back(neighbour);
+                }
+            }
+        }
+    }
+
+    let mut cut = None;
+    if source != sink && reachable.contains(&sink) {
+        let mut path = vec![sink];
+        while let Some(&parent) = parents.get(path.last().unwrap()) {
+            path.push(parent);
+        }
+        path.reverse();
+        violations.push(Violation::NotMaximal { path });
+    } else {
+        let cut_capacity = capacities.iter()
+            .filter(|&(&(from, to), _)| reachable.contains(&from) && !reachable.contains(&to))
+            .fold(C::Sum::zero(), |sum, (_, &capacity)| add_capacities(sum, capacity));
+        if cut_capacity.close_to(amount) {
+            cut = Some(reachable);
+        }
+    }
+
+    FlowReport { amount, violations, cut }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use w1_flows_in_networks::dinic;
+    use w1_flows_in_networks::max_flow;
+    use w1_flows_in_networks::test_utils::evacuation_graph;
+    use w1_flows_in_networks::test_utils::for_rnd_edges;
+
+    #[test]
+    fn test1() {
+        let graph = evacuation_graph();
+
+        let report = verify_flow(&graph, 1, 5, &max_flow(&graph, 1, 5));
+
+        assert!(report.is_max_flow(), "{:?}", report);
+        assert_eq!(report.amount, 6);
+        assert_eq!(report.cut, Some([1, 3, 4].iter().cloned().collect()));
+    }
+
+    #[test]
+    fn test_violations() {
+        let graph: Vec<Edge> = vec![
+            Edge { from: 1, to: 2, capacity: 2 },
+            Edge { from: 2, to: 3, capacity: 5 },
+            Edge { from: 1, to: 3, capacity: 1 },
+        ];
+        let mut flows: Flows<u32> = HashMap::new();
+        flows.entry(1).or_default().insert(2, 3);
+        flows.entry(2).or_default().insert(3, 2);
+
+        let report = verify_flow(&graph, 1, 3, &flows);
+
+        assert!(!report.is_max_flow());
+        assert_eq!(report.amount, 3);
+        assert!(report.violations.contains(&Violation::Capacity { from: 1, to: 2, flow: 3, capacity: 2 }));
+        assert!(report.violations.contains(&Violation::Conservation { vert: 2, inflow: 3, outflow: 2 }));
+        assert!(report.violations.contains(&Violation::NotMaximal { path: vec![1, 3] }));
+        assert_eq!(report.violations.len(), 3);
+    }
+
+    #[test]
+    fn test_not_maximal() {
+        let graph: Vec<Edge> = vec![
+            Edge { from: 1, to: 2, capacity: 1 },
+            Edge { from: 2, to: 3, capacity: 1 },
+            Edge { from: 1, to: 3, capacity: 1 },
+        ];
+        let mut flows: Flows<u32> = HashMap::new();
+        flows.entry(1).or_default().insert(3, 1);
+
+        let report = verify_flow(&graph, 1, 3, &flows);
+
+        assert_eq!(report.violations, [Violation::NotMaximal { path: vec![1, 2, 3] }]);
+        assert_eq!(report.cut, None);
+    }
+
+    #[test]
+    fn test_amount_wider_than_capacities() {
+        let graph = vec![
+            Edge { from: 1, to: 2, capacity: 60_000u16 },
+            Edge { from: 2, to: 4, capacity: 60_000 },
+            Edge { from: 1, to: 3, capacity: 60_000 },
+            Edge { from: 3, to: 4, capacity: 60_000 },
+        ];
+
+        let report = verify_flow(&graph, 1, 4, &max_flow(&graph, 1, 4));
+
+        assert!(report.is_max_flow(), "{:?}", report);
+        assert_eq!(report.amount, 120_000);
+    }
+
+    #[test]
+    fn test_f64_rounding() {
+        let graph = vec![
+            Edge { from: 1, to: 2, capacity: 1.0 },
+            Edge { from: 1, to: 3, capacity: 1.0 },
+            Edge { from: 2, to: 4, capacity: 1.0 },
+            Edge { from: 3, to: 4, capacity: 1.0 },
+            Edge { from: 4, to: 5, capacity: 0.3 },
+        ];
+        // 0.1 + 0.2 enters vertex 4 and is a bit more than the 0.3 leaving it
+        let mut flows: Flows<f64> = HashMap::new();
+        flows.entry(1).or_default().insert(2, 0.1);
+        flows.entry(1).or_default().insert(3, 0.2);
+        flows.entry(2).or_default().insert(4, 0.1);
+        flows.entry(3).or_default().insert(4, 0.2);
+        flows.entry(4).or_default().insert(5, 0.3);
+
+        let report = verify_flow(&graph, 1, 5, &flows);
+
+        assert!(report.is_max_flow(), "{:?}", report);
+        assert_eq!(report.cut, Some([1, 2, 3, 4].iter().cloned().collect()));
+    }
+
+    #[test]
+    fn test_rnd() {
+        for_rnd_edges(|edges, expected| {
+            let report = verify_flow(edges, 1, 2, &dinic::max_flow(edges, 1, 2));
+
+            assert!(report.is_max_flow(), "edges {:?}, report {:?}", edges, report);
+            assert_eq!(report.amount, expected);
+        });
+    }
+}